@@ -1,88 +1,454 @@
 #![allow(non_snake_case)]
 #![allow(dead_code)]
 
-use std::sync::mpsc;
+mod response;
+mod stream;
 
-use explorer::Explorer;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+
+use explorer::{
+    CancellationRegistry, Command, Explorer, ExplorerError, RepoHandle, Request, RequestId,
+    Response,
+};
 use napi::bindgen_prelude::*;
 use napi::{
-    threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode},
-    JsFunction, JsUndefined,
+    threadsafe_function::ThreadsafeFunctionCallMode,
+    JsFunction, JsObject, JsUndefined, JsUnknown,
 };
 use napi_derive::napi;
+use serde_json::{json, Value};
+
+use crate::response::{is_final, settle};
+use crate::stream::ResponseStream;
+
+type Callback = Box<dyn Fn(Response) + Send + Sync>;
+
+/// Ids of requests issued through the typed methods start above the largest
+/// integer JavaScript represents exactly, so they cannot collide with ids that
+/// callers of `handle` choose.
+const TYPED_REQUEST_IDS: RequestId = 1 << 53;
 
 enum ModuleMessage {
-    Job(String, ThreadsafeFunction<String>),
+    Job(Box<Request>, Callback),
+    /// An `open_repository` request and the handle the repository is opened under.
+    Open(Box<Request>, RepoHandle, Callback),
     Close(),
 }
 
+/// A thread owning its own `Explorer` and therefore its own repository handles.
+struct Worker {
+    tx: mpsc::Sender<ModuleMessage>,
+
+    /// Jobs sent to this worker that it has not finished yet.
+    pending: Arc<AtomicUsize>,
+
+    cancellations: CancellationRegistry,
+}
+
+impl Worker {
+    fn spawn(cancellations: CancellationRegistry, pool_size: u32) -> Self {
+        let (tx, rx) = mpsc::channel::<ModuleMessage>();
+        let pending = Arc::new(AtomicUsize::new(0));
+
+        let mut explorer: Explorer = Explorer::new();
+        explorer.set_cancellation_registry(cancellations.clone());
+        explorer.set_concurrent_requests(pool_size);
+
+        let counter = pending.clone();
+        std::thread::spawn(move || {
+            while let Ok(message) = rx.recv() {
+                match message {
+                    ModuleMessage::Job(request, callback) => {
+                        explorer.handle(*request, callback);
+                        counter.fetch_sub(1, Ordering::Relaxed);
+                    }
+                    ModuleMessage::Open(request, handle, callback) => {
+                        explorer.set_next_repo_handle(handle);
+                        explorer.handle(*request, callback);
+                        counter.fetch_sub(1, Ordering::Relaxed);
+                    }
+                    ModuleMessage::Close() => break,
+                }
+            }
+        });
+
+        Worker {
+            tx,
+            pending,
+            cancellations,
+        }
+    }
+
+    fn send(&self, request: Request, callback: Callback) {
+        self.enqueue(request.id, ModuleMessage::Job(Box::new(request), callback));
+    }
+
+    fn send_open(&self, request: Request, handle: RepoHandle, callback: Callback) {
+        self.enqueue(request.id, ModuleMessage::Open(Box::new(request), handle, callback));
+    }
+
+    fn enqueue(&self, id: Option<RequestId>, message: ModuleMessage) {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        if let Some(id) = id {
+            self.cancellations.register(id);
+        }
+        self.tx.send(message).unwrap();
+    }
+}
+
+/// Requests that change which repositories are open. They run on every worker so
+/// that all workers hold the same repositories; the handles of opened repositories
+/// are assigned by the module, so they are the same on every worker.
+fn is_broadcast(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::OpenRepository(_) | Command::CloseRepository(_) | Command::Shutdown(_)
+    )
+}
+
 #[napi(custom_finalize)]
 struct Module {
-    tx: mpsc::Sender<ModuleMessage>,
+    workers: Vec<Worker>,
+    cancellations: CancellationRegistry,
+    next_id: AtomicU64,
+    next_handle: AtomicU32,
 }
 
 #[napi]
 impl ObjectFinalize for Module {
     fn finalize(self, _: Env) -> Result<()> {
-        let _ = self.tx.send(ModuleMessage::Close());
+        for worker in &self.workers {
+            let _ = worker.tx.send(ModuleMessage::Close());
+        }
         Ok(())
     }
 }
 
 #[napi]
 impl Module {
+    /// Creates a module backed by `poolSize` workers, one per available CPU by default.
     #[napi(constructor)]
-    pub fn new() -> Result<Self> {
-        let (tx, rx) = mpsc::channel::<ModuleMessage>();
+    pub fn new(poolSize: Option<u32>) -> Result<Self> {
+        let size = match poolSize {
+            Some(0) => {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    "poolSize must be at least 1".to_string(),
+                ))
+            }
+            Some(size) => size as usize,
+            None => std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        };
 
-        std::thread::spawn(move || {
-            let mut explorer: Explorer = Explorer::new();
+        let cancellations = CancellationRegistry::new();
+        let workers = (0..size)
+            .map(|_| Worker::spawn(cancellations.clone(), size as u32))
+            .collect();
 
-            while let Ok(message) = rx.recv() {
-                match message {
-                    ModuleMessage::Job(payload, callback) => {
+        Ok(Self {
+            workers,
+            cancellations,
+            next_id: AtomicU64::new(TYPED_REQUEST_IDS),
+            next_handle: AtomicU32::new(0),
+        })
+    }
 
-                        let request = Explorer::deserialize_request(payload);
+    #[napi]
+    pub fn poolSize(&self) -> u32 {
+        self.workers.len() as u32
+    }
 
-                        if request.is_err() {
-                            callback.call(
-                                Err(napi::Error::from_reason("Invalid request")),
-                                ThreadsafeFunctionCallMode::NonBlocking,
-                            );
-                            continue;
-                        }
+    #[napi]
+    pub fn handle(&self, env: Env, payload: String, js_cb: JsFunction) -> Result<JsUndefined> {
+        let tsf = env.create_threadsafe_function(&js_cb, 0, |ctx| Ok(vec![ctx.value]));
 
-                        let request = request.unwrap();
+        if tsf.is_err() {
+            panic!("Failed to create threadsafe function");
+        }
 
-                        explorer.handle(request, move |response| {
-                            let response = Explorer::serialize_response(response).unwrap();
+        let callback = tsf.unwrap();
 
-                            callback.call(
-                                Ok(response),
-                                ThreadsafeFunctionCallMode::NonBlocking,
-                            );
-                        });
-                    }
-                    ModuleMessage::Close() => break,
-                }
+        let request = match Explorer::deserialize_request(payload.clone()) {
+            Ok(request) => request,
+            Err(err) => {
+                let response = Response::error(
+                    Request::peek_id(&payload),
+                    &ExplorerError::from(err),
+                );
+                callback.call(
+                    Ok(Explorer::serialize_response(response).unwrap()),
+                    ThreadsafeFunctionCallMode::NonBlocking,
+                );
+                return env.get_undefined();
             }
-        });
+        };
+
+        // Cancellation must not queue behind the request it is meant to stop,
+        // so it is applied directly instead of being sent to a worker.
+        if let Command::Cancel(params) = &request.command {
+            let cancelled = self.cancellations.cancel(params.id);
+            let response =
+                Explorer::serialize_response(Response::data(request.id, cancelled, true)).unwrap();
+            callback.call(Ok(response), ThreadsafeFunctionCallMode::NonBlocking);
+            return env.get_undefined();
+        }
+
+        self.submit(
+            request,
+            Box::new(move |response| {
+                let response = Explorer::serialize_response(response).unwrap();
+                callback.call(Ok(response), ThreadsafeFunctionCallMode::NonBlocking);
+            }),
+        );
 
-        Ok(Self { tx })
+        env.get_undefined()
     }
+}
 
-    #[napi]
-    pub fn handle(&self, env: Env, payload: String, js_cb: JsFunction) -> Result<JsUndefined> {
-        let tsf = env.create_threadsafe_function(&js_cb, 0, |ctx| Ok(vec![ctx.value]));
+impl Module {
+    /// Queues `request` on the least busy worker, or on every worker if it changes
+    /// which repositories are open. Broadcast requests are only answered by the
+    /// first worker.
+    fn submit(&self, request: Request, callback: Callback) {
+        if is_broadcast(&request.command) {
+            let handle = matches!(request.command, Command::OpenRepository(_))
+                .then(|| self.next_handle.fetch_add(1, Ordering::Relaxed));
+            let send = |worker: &Worker, request: Request, callback: Callback| match handle {
+                Some(handle) => worker.send_open(request, handle, callback),
+                None => worker.send(request, callback),
+            };
 
-        if tsf.is_err() {
-            panic!("Failed to create threadsafe function");
+            let payload = serde_json::to_string(&request).unwrap();
+            for worker in &self.workers[1..] {
+                let copy = Explorer::deserialize_request(payload.clone()).unwrap();
+                send(worker, copy, Box::new(|_| {}));
+            }
+            send(&self.workers[0], request, callback);
+            return;
         }
 
-        self.tx
-            .send(ModuleMessage::Job(payload, tsf.unwrap()))
+        let worker = self
+            .workers
+            .iter()
+            .min_by_key(|w| w.pending.load(Ordering::Relaxed))
             .unwrap();
+        worker.send(request, callback);
+    }
 
-        env.get_undefined()
+    fn build_request(
+        &self,
+        env: &Env,
+        method: &str,
+        params: Option<JsUnknown>,
+        repo: Option<RepoHandle>,
+    ) -> Result<Request> {
+        let params: Value = match params {
+            Some(params) => env.from_js_value(params)?,
+            None => json!({}),
+        };
+
+        let request = json!({
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "repo": repo,
+            "method": method,
+            "params": params,
+        });
+
+        serde_json::from_value(request)
+            .map_err(|e| Error::new(Status::InvalidArg, ExplorerError::from(e).to_string()))
+    }
+
+    /// Runs a request and returns a promise of its result.
+    fn call(
+        &self,
+        env: Env,
+        method: &str,
+        params: Option<JsUnknown>,
+        repo: Option<RepoHandle>,
+    ) -> Result<JsObject> {
+        let request = self.build_request(&env, method, params, repo)?;
+        let (deferred, promise) = env.create_deferred()?;
+        let deferred = Mutex::new(Some(deferred));
+
+        self.submit(
+            request,
+            Box::new(move |response| {
+                let response = serde_json::to_value(response).unwrap();
+                if is_final(&response) {
+                    if let Some(deferred) = deferred.lock().unwrap().take() {
+                        settle(deferred, response);
+                    }
+                }
+            }),
+        );
+
+        Ok(promise)
+    }
+
+    /// Runs a streaming request and returns its messages as a `ResponseStream`.
+    fn open_stream(
+        &self,
+        env: Env,
+        method: &str,
+        params: Option<JsUnknown>,
+        repo: Option<RepoHandle>,
+    ) -> Result<ResponseStream> {
+        let request = self.build_request(&env, method, params, repo)?;
+        let id = request.id.unwrap();
+
+        let (stream, callback) = ResponseStream::new(id, self.cancellations.clone());
+        self.submit(request, Box::new(callback));
+
+        Ok(stream)
+    }
+}
+
+/// Typed counterparts of the protocol methods. Parameters and results are plain
+/// JS objects shaped like the protocol's JSON; errors reject with an `Error` whose
+/// `code` is the protocol's error code. The precise TypeScript signatures live on
+/// the `Explorer` class in `ts-src/explorer.ts`.
+#[napi]
+impl Module {
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn openRepository(&self, env: Env, params: JsUnknown) -> Result<JsObject> {
+        self.call(env, "open_repository", Some(params), None)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn closeRepository(&self, env: Env, params: JsUnknown) -> Result<JsObject> {
+        self.call(env, "close_repository", Some(params), None)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn listRepositories(&self, env: Env) -> Result<JsObject> {
+        self.call(env, "list_repositories", None, None)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getInitialData(&self, env: Env, repo: Option<u32>) -> Result<JsObject> {
+        self.call(env, "get_initial_data", None, repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getAuthors(
+        &self,
+        env: Env,
+        params: Option<JsUnknown>,
+        repo: Option<u32>,
+    ) -> Result<JsObject> {
+        self.call(env, "get_authors", params, repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getBranches(&self, env: Env, repo: Option<u32>) -> Result<JsObject> {
+        self.call(env, "get_branches", None, repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getGitGraph(&self, env: Env, repo: Option<u32>) -> Result<JsObject> {
+        self.call(env, "get_git_graph", None, repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getBlame(&self, env: Env, params: JsUnknown, repo: Option<u32>) -> Result<JsObject> {
+        self.call(env, "get_blame", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getFileTree(&self, env: Env, params: JsUnknown, repo: Option<u32>) -> Result<JsObject> {
+        self.call(env, "get_file_tree", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getFileContent(
+        &self,
+        env: Env,
+        params: JsUnknown,
+        repo: Option<u32>,
+    ) -> Result<JsObject> {
+        self.call(env, "get_file_content", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getCommit(&self, env: Env, params: JsUnknown, repo: Option<u32>) -> Result<JsObject> {
+        self.call(env, "get_commit", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getDiff(&self, env: Env, params: JsUnknown, repo: Option<u32>) -> Result<JsObject> {
+        self.call(env, "get_diff", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn mapLegacyAuthorIds(
+        &self,
+        env: Env,
+        params: JsUnknown,
+        repo: Option<u32>,
+    ) -> Result<JsObject> {
+        self.call(env, "map_legacy_author_ids", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getCommitsForBranch(
+        &self,
+        env: Env,
+        params: JsUnknown,
+        repo: Option<u32>,
+    ) -> Result<JsObject> {
+        self.call(env, "get_commits_for_branch", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getCommitsForTimeRange(
+        &self,
+        env: Env,
+        params: JsUnknown,
+        repo: Option<u32>,
+    ) -> Result<JsObject> {
+        self.call(env, "get_commits_for_time_range", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn isValidRev(&self, env: Env, params: JsUnknown, repo: Option<u32>) -> Result<JsObject> {
+        self.call(env, "is_valid_rev", Some(params), repo)
+    }
+
+    #[napi(ts_return_type = "Promise<unknown>")]
+    pub fn getCapabilities(&self, env: Env) -> Result<JsObject> {
+        self.call(env, "get_capabilities", None, None)
+    }
+
+    #[napi]
+    pub fn streamAuthors(
+        &self,
+        env: Env,
+        params: JsUnknown,
+        repo: Option<u32>,
+    ) -> Result<ResponseStream> {
+        self.open_stream(env, "stream_authors", Some(params), repo)
+    }
+
+    #[napi]
+    pub fn streamCommits(
+        &self,
+        env: Env,
+        params: JsUnknown,
+        repo: Option<u32>,
+    ) -> Result<ResponseStream> {
+        self.open_stream(env, "stream_commits", Some(params), repo)
+    }
+
+    #[napi]
+    pub fn streamFileTree(
+        &self,
+        env: Env,
+        params: JsUnknown,
+        repo: Option<u32>,
+    ) -> Result<ResponseStream> {
+        self.open_stream(env, "stream_file_tree", Some(params), repo)
     }
 }
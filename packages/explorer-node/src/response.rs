@@ -0,0 +1,85 @@
+use explorer::ErrorCode;
+use napi::{Env, Error, JsDeferred, JsObject, JsUnknown, Result, Status};
+use serde_json::Value;
+
+pub(crate) type Resolver = Box<dyn FnOnce(Env) -> Result<JsUnknown> + Send>;
+pub(crate) type Deferred = JsDeferred<JsUnknown, Resolver>;
+
+/// Whether `response` is the last message of its request.
+pub(crate) fn is_final(response: &Value) -> bool {
+    response.get("error").is_some() || response["end"] == true
+}
+
+/// Builds a JS `Error` that carries the protocol `code` (and `git` details, if any)
+/// of an error response, so callers can branch on it like on the JSON protocol.
+fn to_js_error(env: Env, response: &Value) -> Error {
+    let message = response["error"].as_str().unwrap_or_default().to_string();
+
+    let mut error = match env.create_error(Error::new(Status::GenericFailure, message)) {
+        Ok(error) => error,
+        Err(err) => return err,
+    };
+
+    let mut annotate = || -> Result<()> {
+        error.set_named_property("code", env.to_js_value(&response["code"])?)?;
+        if let Some(git) = response.get("git") {
+            error.set_named_property("git", env.to_js_value(git)?)?;
+        }
+        Ok(())
+    };
+
+    match annotate() {
+        Ok(()) => Error::from(error.into_unknown()),
+        Err(err) => err,
+    }
+}
+
+fn cancelled(env: Env) -> Error {
+    to_js_error(
+        env,
+        &serde_json::json!({ "error": "Request was cancelled", "code": ErrorCode::Cancelled }),
+    )
+}
+
+/// Settles the promise of a request with its final response.
+pub(crate) fn settle(deferred: Deferred, response: Value) {
+    deferred.resolve(Box::new(move |env| {
+        if response.get("error").is_some() {
+            return Err(to_js_error(env, &response));
+        }
+
+        if response["cancelled"] == true {
+            return Err(cancelled(env));
+        }
+
+        env.to_js_value(&response["data"])
+    }));
+}
+
+fn iterator_result(env: Env, done: bool, value: &Value) -> Result<JsUnknown> {
+    let mut result: JsObject = env.create_object()?;
+    result.set_named_property("done", env.get_boolean(done)?)?;
+    result.set_named_property("value", env.to_js_value(value)?)?;
+    Ok(result.into_unknown())
+}
+
+/// Settles a pending `next()` of a stream with one of its messages. The final
+/// message completes the iteration and carries the result of the stream, if any.
+pub(crate) fn settle_next(deferred: Deferred, response: Value) {
+    deferred.resolve(Box::new(move |env| {
+        if response.get("error").is_some() {
+            return Err(to_js_error(env, &response));
+        }
+
+        if response["cancelled"] == true {
+            return iterator_result(env, true, &Value::Null);
+        }
+
+        iterator_result(env, response["end"] == true, &response["data"])
+    }));
+}
+
+/// Resolves a `next()` of a stream that has already completed.
+pub(crate) fn settle_done(deferred: Deferred) {
+    deferred.resolve(Box::new(|env| iterator_result(env, true, &Value::Null)));
+}
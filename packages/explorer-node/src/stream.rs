@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use explorer::{CancellationRegistry, RequestId, Response};
+use napi::{Env, Error, JsObject, Result, Status};
+use napi_derive::napi;
+use serde_json::Value;
+
+use crate::response::{is_final, settle_done, settle_next, Deferred};
+
+#[derive(Default)]
+struct StreamState {
+    /// Messages that arrived before anyone asked for them.
+    queue: VecDeque<Value>,
+
+    /// A `next()` waiting for the next message.
+    waiting: Option<Deferred>,
+
+    /// Set once the final message has been handed out.
+    finished: bool,
+}
+
+/// The messages of a streaming request, consumed through `next()`.
+///
+/// The TypeScript `Explorer` wraps it into an async iterator.
+#[napi]
+pub struct ResponseStream {
+    id: RequestId,
+    state: Arc<Mutex<StreamState>>,
+    cancellations: CancellationRegistry,
+}
+
+impl ResponseStream {
+    /// Creates the stream and the callback the explorer reports the request's messages to.
+    pub(crate) fn new(
+        id: RequestId,
+        cancellations: CancellationRegistry,
+    ) -> (Self, impl Fn(Response) + Send + Sync + 'static) {
+        let state = Arc::new(Mutex::new(StreamState::default()));
+
+        let shared = state.clone();
+        let callback = move |response: Response| {
+            let response = serde_json::to_value(response).unwrap();
+            let mut state = shared.lock().unwrap();
+
+            match state.waiting.take() {
+                Some(deferred) => {
+                    state.finished = is_final(&response);
+                    settle_next(deferred, response);
+                }
+                None => state.queue.push_back(response),
+            }
+        };
+
+        let stream = ResponseStream {
+            id,
+            state,
+            cancellations,
+        };
+
+        (stream, callback)
+    }
+}
+
+#[napi]
+impl ResponseStream {
+    #[napi(ts_return_type = "Promise<{ done: boolean; value: unknown }>")]
+    pub fn next(&self, env: Env) -> Result<JsObject> {
+        let (deferred, promise) = env.create_deferred()?;
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(response) = state.queue.pop_front() {
+            state.finished = is_final(&response);
+            settle_next(deferred, response);
+        } else if state.finished {
+            settle_done(deferred);
+        } else if state.waiting.is_some() {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "next() called before the previous call settled".to_string(),
+            ));
+        } else {
+            state.waiting = Some(deferred);
+        }
+
+        Ok(promise)
+    }
+
+    /// Stops the request if it is still running. Messages that are already queued
+    /// can still be read; the stream then ends as cancelled.
+    #[napi]
+    pub fn cancel(&self) {
+        if !self.state.lock().unwrap().finished {
+            self.cancellations.cancel(self.id);
+        }
+    }
+}
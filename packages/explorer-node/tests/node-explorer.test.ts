@@ -2,6 +2,7 @@
 import { execSync } from "node:child_process";
 import { expect, test } from "vitest";
 
+import { Explorer } from "../ts-src/explorer";
 import { ExplorerPool } from "../ts-src/explorer-pool";
 
 const rootRepoPath = execSync("git rev-parse --show-toplevel").toString().trim();
@@ -57,3 +58,125 @@ test(
     timeout: 1000,
   },
 );
+
+test(
+  "runs requests on every worker of the pool",
+  async () => {
+    const pool = new ExplorerPool(4);
+    expect(await pool.getNumWorkers()).toBe(4);
+
+    await new Promise<void>((resolve) => {
+      pool.request("open_repository", { path: rootRepoPath }, (response) => {
+        expect(response).toMatchObject({ data: { success: true } });
+        resolve();
+      });
+    });
+
+    const files = ["README.md", "package.json", "Cargo.toml", ".gitignore"];
+    const results = await Promise.all(
+      files.map(
+        (path) =>
+          new Promise<unknown>((resolve) => {
+            pool.request("get_blame", { rev: "HEAD", path }, resolve);
+          }),
+      ),
+    );
+
+    for (const result of results) {
+      expect(result).toMatchObject({ end: true });
+    }
+  },
+  {
+    timeout: 5000,
+  },
+);
+
+test(
+  "typed API returns objects and streams commits",
+  async () => {
+    const explorer = new Explorer(2);
+
+    const { handle } = await explorer.openRepository({ path: rootRepoPath });
+    const commit = await explorer.getCommit({ rev: "HEAD" }, handle);
+    expect(commit.oid).toHaveLength(40);
+
+    await expect(explorer.getCommit({ rev: "does-not-exist" })).rejects.toMatchObject({
+      code: "rev_not_found",
+    });
+
+    let numCommits = 0;
+    for await (const streamed of explorer.streamCommits({})) {
+      expect(streamed.oid).toHaveLength(40);
+      if (++numCommits === 2) {
+        break;
+      }
+    }
+    expect(numCommits).toBe(2);
+  },
+  {
+    timeout: 5000,
+  },
+);
+
+test(
+  "returns every author at once without a limit or cursor",
+  async () => {
+    const explorer = new Explorer(1);
+    await explorer.openRepository({ path: rootRepoPath });
+
+    const authors = await explorer.getAuthors({ order: "num_commits" });
+    expect(Array.isArray(authors)).toBe(true);
+    expect(authors.length).toBeGreaterThan(0);
+
+    const { authors: page } = await explorer.getAuthors({ limit: authors.length });
+    expect(page.map((a) => a.id).sort()).toEqual(authors.map((a) => a.id).sort());
+  },
+  {
+    timeout: 5000,
+  },
+);
+
+test(
+  "pages through authors with a cursor",
+  async () => {
+    const explorer = new Explorer(1);
+    await explorer.openRepository({ path: rootRepoPath });
+
+    const first = await explorer.getAuthors({ limit: 1, order: "num_commits" });
+    expect(first.authors).toHaveLength(1);
+
+    if (first.nextCursor !== null) {
+      const second = await explorer.getAuthors({
+        limit: 1,
+        order: "num_commits",
+        cursor: first.nextCursor,
+      });
+      expect(second.authors[0].id).not.toBe(first.authors[0].id);
+    }
+
+    await expect(
+      explorer.getAuthors({ order: "name", cursor: first.nextCursor ?? "invalid" }),
+    ).rejects.toMatchObject({ code: "invalid_params" });
+  },
+  {
+    timeout: 5000,
+  },
+);
+
+test(
+  "maps current author ids to themselves",
+  async () => {
+    const explorer = new Explorer(1);
+    await explorer.openRepository({ path: rootRepoPath });
+
+    const { authorIdVersion } = await explorer.getCapabilities();
+    const { authors } = await explorer.getAuthors({ limit: 1 });
+    expect(authors[0].id.startsWith(`v${authorIdVersion}-`)).toBe(true);
+
+    const { ids } = await explorer.mapLegacyAuthorIds({ ids: [authors[0].id, "unknown"] });
+    expect(ids).toEqual({ [authors[0].id]: authors[0].id, unknown: null });
+  },
+  {
+    timeout: 5000,
+  },
+);
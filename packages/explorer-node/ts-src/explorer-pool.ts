@@ -11,27 +11,6 @@ import { Explorer } from "./explorer";
 
 export type JobReference = number;
 
-class ExplorerWorker {
-  instance: Explorer;
-  activeJob?: Job<any>;
-
-  constructor() {
-    this.instance = new Explorer();
-  }
-
-  handle<M extends Methods>(job: Job<M>) {
-    if (this.activeJob) {
-      throw new Error("Worker is busy");
-    }
-    this.activeJob = job;
-
-    this.instance.send(job.method, job.params, (resp) => {
-      job.callback(resp);
-      this.activeJob = undefined;
-    });
-  }
-}
-
 interface Job<M extends Methods> {
   id: JobReference;
   priority: number;
@@ -43,12 +22,19 @@ interface Job<M extends Methods> {
 export class ExplorerPool implements ExplorerPoolI<JobReference> {
   counter: number;
   jobs: Job<any>[];
+  /** The jobs handed to the explorer, with the request id it knows them by. */
+  activeJobs: Map<JobReference, number>;
 
-  worker: ExplorerWorker;
+  /**
+   * The native module runs up to `poolSize` requests at the same time, each on a
+   * worker thread with its own repository handles.
+   */
+  explorer: Explorer;
 
-  constructor() {
+  constructor(poolSize?: number) {
     this.jobs = [];
-    this.worker = new ExplorerWorker();
+    this.activeJobs = new Map();
+    this.explorer = new Explorer(poolSize);
     this.counter = 0;
   }
 
@@ -57,14 +43,11 @@ export class ExplorerPool implements ExplorerPoolI<JobReference> {
   }
 
   getNumWorkers(): Promise<number> {
-    return Promise.resolve(1);
+    return Promise.resolve(this.explorer.poolSize);
   }
 
   getNumBusyWorkers(): Promise<number> {
-    if (this.worker.activeJob) {
-      return Promise.resolve(1);
-    }
-    return Promise.resolve(0);
+    return Promise.resolve(Math.min(this.activeJobs.size, this.explorer.poolSize));
   }
 
   setPoolSize(_: number): Promise<void> {
@@ -97,6 +80,13 @@ export class ExplorerPool implements ExplorerPoolI<JobReference> {
     }
   }
   cancel(ref: number): void {
+    const requestId = this.activeJobs.get(ref);
+    if (requestId !== undefined) {
+      // the job's callback receives the cancellation once the explorer stops it
+      this.explorer.cancel(requestId);
+      return;
+    }
+
     const job = this.jobs.find((j) => j.id === ref);
     if (!job) {
       return;
@@ -114,22 +104,34 @@ export class ExplorerPool implements ExplorerPoolI<JobReference> {
   }
 
   private tryDispatchJob() {
-    if (this.worker.activeJob) {
-      return;
-    }
-
-    const job = this.jobs.sort((a, b) => {
-      if (a.priority === b.priority) {
-        return b.id - a.id;
+    while (this.activeJobs.size < this.explorer.poolSize) {
+      const job = this.jobs.sort((a, b) => {
+        if (a.priority === b.priority) {
+          return b.id - a.id;
+        }
+        return b.priority - a.priority;
+      })[0];
+
+      if (!job) {
+        return;
       }
-      return b.priority - a.priority;
-    })[0];
 
-    if (!job) {
-      return;
+      this.removeJob(job.id);
+      this.dispatch(job);
     }
+  }
+
+  private dispatch<M extends Methods>(job: Job<M>) {
+    const requestId = this.explorer.send(job.method, job.params, (resp) => {
+      job.callback(resp);
+
+      if (resp.error || resp.end) {
+        this.activeJobs.delete(job.id);
+        this.tryDispatchJob();
+      }
+    });
 
-    this.worker.handle(job);
+    this.activeJobs.set(job.id, requestId);
   }
 
   on(
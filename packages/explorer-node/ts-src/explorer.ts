@@ -1,14 +1,68 @@
-import { Callback, ExplorerI, Methods, Params } from "@giz/explorer";
-import { Module } from "../build";
+import {
+  Author,
+  AuthorPage,
+  Callback,
+  ExplorerI,
+  FinalPayload,
+  IntermediatePayload,
+  JobCanceledError,
+  Methods,
+  Params,
+  RepoHandle,
+} from "@giz/explorer";
+import { Module, ResponseStream } from "../build";
+
+type Result<M extends Methods> = Promise<FinalPayload<M>>;
+type Stream<M extends Methods> = AsyncGenerator<IntermediatePayload<M>, FinalPayload<M>>;
+
+/**
+ * Adapts a native `ResponseStream` to an async iterator. Leaving the iteration
+ * early, e.g. with `break`, cancels the request.
+ */
+async function* iterate<M extends Methods>(stream: ResponseStream): Stream<M> {
+  try {
+    for (;;) {
+      const next = await stream.next();
+      if (next.done) {
+        return next.value as FinalPayload<M>;
+      }
+      yield next.value as IntermediatePayload<M>;
+    }
+  } finally {
+    stream.cancel();
+  }
+}
 
 export class Explorer implements ExplorerI {
   private module: Module;
+  private counter = 0;
+
+  /**
+   * @param poolSize Number of native worker threads, each with its own repository handles.
+   * Defaults to the number of available CPUs.
+   */
+  constructor(poolSize?: number) {
+    this.module = new Module(poolSize);
+  }
 
-  constructor() {
-    this.module = new Module();
+  get poolSize(): number {
+    return this.module.poolSize();
   }
-  send<M extends Methods>(method: M, params: Params<M>, cb: Callback<M>): void {
+
+  /**
+   * Sends a request and reports its responses to `cb`. Returns the id of the
+   * request, which `cancel` takes.
+   */
+  send<M extends Methods>(
+    method: M,
+    params: Params<M>,
+    cb: Callback<M>,
+    repo?: RepoHandle,
+  ): number {
+    const id = this.counter++;
     const payload = JSON.stringify({
+      id,
+      repo,
       method,
       params,
     });
@@ -30,6 +84,13 @@ export class Explorer implements ExplorerI {
         return;
       }
 
+      if (data.cancelled) {
+        cb({
+          error: new JobCanceledError(),
+        });
+        return;
+      }
+
       if (data.end) {
         cb({
           data: data.data,
@@ -42,5 +103,124 @@ export class Explorer implements ExplorerI {
         });
       }
     });
+
+    return id;
+  }
+
+  /**
+   * Asks a request started with `send` to stop. Its callback then receives a
+   * `JobCanceledError`, unless the request finished first.
+   */
+  cancel(id: number): void {
+    const payload = JSON.stringify({
+      method: "cancel",
+      params: { id },
+    });
+
+    this.module.handle(payload, () => {});
+  }
+
+  openRepository(params: Params<"open_repository">): Result<"open_repository"> {
+    return this.module.openRepository(params) as Result<"open_repository">;
+  }
+
+  closeRepository(params: Params<"close_repository">): Result<"close_repository"> {
+    return this.module.closeRepository(params) as Result<"close_repository">;
+  }
+
+  listRepositories(): Result<"list_repositories"> {
+    return this.module.listRepositories() as Result<"list_repositories">;
+  }
+
+  getInitialData(repo?: RepoHandle): Result<"get_initial_data"> {
+    return this.module.getInitialData(repo) as Result<"get_initial_data">;
+  }
+
+  /**
+   * Returns every author at once, or a page of them if `params` has a `limit` or
+   * `cursor`.
+   */
+  getAuthors(
+    params?: Params<"get_authors"> & { limit?: undefined; cursor?: undefined },
+    repo?: RepoHandle,
+  ): Promise<Author[]>;
+  getAuthors(params: Params<"get_authors">, repo?: RepoHandle): Promise<AuthorPage>;
+  getAuthors(params: Params<"get_authors"> = {}, repo?: RepoHandle): Result<"get_authors"> {
+    return this.module.getAuthors(params, repo) as Result<"get_authors">;
+  }
+
+  getBranches(repo?: RepoHandle): Result<"get_branches"> {
+    return this.module.getBranches(repo) as Result<"get_branches">;
+  }
+
+  getGitGraph(repo?: RepoHandle): Result<"get_git_graph"> {
+    return this.module.getGitGraph(repo) as Result<"get_git_graph">;
+  }
+
+  getBlame(params: Params<"get_blame">, repo?: RepoHandle): Result<"get_blame"> {
+    return this.module.getBlame(params, repo) as Result<"get_blame">;
+  }
+
+  getFileTree(params: Params<"get_file_tree">, repo?: RepoHandle): Result<"get_file_tree"> {
+    return this.module.getFileTree(params, repo) as Result<"get_file_tree">;
+  }
+
+  getFileContent(
+    params: Params<"get_file_content">,
+    repo?: RepoHandle,
+  ): Result<"get_file_content"> {
+    return this.module.getFileContent(params, repo) as Result<"get_file_content">;
+  }
+
+  getCommit(params: Params<"get_commit">, repo?: RepoHandle): Result<"get_commit"> {
+    return this.module.getCommit(params, repo) as Result<"get_commit">;
+  }
+
+  getDiff(params: Params<"get_diff">, repo?: RepoHandle): Result<"get_diff"> {
+    return this.module.getDiff(params, repo) as Result<"get_diff">;
+  }
+
+  mapLegacyAuthorIds(
+    params: Params<"map_legacy_author_ids">,
+    repo?: RepoHandle,
+  ): Result<"map_legacy_author_ids"> {
+    return this.module.mapLegacyAuthorIds(params, repo) as Result<"map_legacy_author_ids">;
+  }
+
+  getCommitsForBranch(
+    params: Params<"get_commits_for_branch">,
+    repo?: RepoHandle,
+  ): Result<"get_commits_for_branch"> {
+    return this.module.getCommitsForBranch(params, repo) as Result<"get_commits_for_branch">;
+  }
+
+  getCommitsForTimeRange(
+    params: Params<"get_commits_for_time_range">,
+    repo?: RepoHandle,
+  ): Result<"get_commits_for_time_range"> {
+    return this.module.getCommitsForTimeRange(params, repo) as Result<"get_commits_for_time_range">;
+  }
+
+  isValidRev(params: Params<"is_valid_rev">, repo?: RepoHandle): Result<"is_valid_rev"> {
+    return this.module.isValidRev(params, repo) as Result<"is_valid_rev">;
+  }
+
+  getCapabilities(): Result<"get_capabilities"> {
+    return this.module.getCapabilities() as Result<"get_capabilities">;
+  }
+
+  streamAuthors(params: Params<"stream_authors">, repo?: RepoHandle): Stream<"stream_authors"> {
+    return iterate(this.module.streamAuthors(params, repo));
+  }
+
+  streamCommits(params: Params<"stream_commits">, repo?: RepoHandle): Stream<"stream_commits"> {
+    return iterate(this.module.streamCommits(params, repo));
+  }
+
+  streamFileTree(
+    params: Params<"stream_file_tree">,
+    repo?: RepoHandle,
+  ): Stream<"stream_file_tree"> {
+    return iterate(this.module.streamFileTree(params, repo));
   }
 }
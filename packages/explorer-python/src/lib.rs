@@ -0,0 +1,690 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+
+use explorer::{CancellationRegistry, RepoHandle, Request, RequestId, METHODS};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde_json::{json, Map, Value};
+
+create_exception!(
+    giz_explorer,
+    ExplorerError,
+    PyException,
+    "Raised when a request fails. The protocol error code is available as `code`."
+);
+
+/// A request together with the channel its responses are reported to.
+type Job = (Request, mpsc::Sender<Value>);
+
+fn to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.into_pyobject(py)?.into_any()
+            } else if let Some(u) = n.as_u64() {
+                u.into_pyobject(py)?.into_any()
+            } else {
+                n.as_f64().unwrap_or(f64::NAN).into_pyobject(py)?.into_any()
+            }
+        }
+        Value::String(s) => PyString::new(py, s).into_any(),
+        Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(|v| to_py(py, v))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, items)?.into_any()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                dict.set_item(key, to_py(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+fn from_py(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if value.is_none() {
+        Ok(Value::Null)
+    } else if value.is_instance_of::<PyBool>() {
+        Ok(Value::Bool(value.extract()?))
+    } else if value.is_instance_of::<PyInt>() {
+        Ok(json!(value.extract::<i64>()?))
+    } else if value.is_instance_of::<PyFloat>() {
+        Ok(json!(value.extract::<f64>()?))
+    } else if value.is_instance_of::<PyString>() {
+        Ok(Value::String(value.extract()?))
+    } else if let Ok(dict) = value.downcast::<PyDict>() {
+        let mut map = Map::new();
+        for (key, value) in dict.iter() {
+            map.insert(key.extract::<String>()?, from_py(&value)?);
+        }
+        Ok(Value::Object(map))
+    } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        value.try_iter()?.map(|item| from_py(&item?)).collect()
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "cannot convert {} to a request parameter",
+            value.get_type().name()?
+        )))
+    }
+}
+
+fn to_py_err(py: Python<'_>, response: &Value) -> PyErr {
+    let message = response["error"].as_str().unwrap_or_default().to_string();
+    let err = ExplorerError::new_err(message);
+
+    let value = err.value(py);
+    if let Ok(code) = to_py(py, &response["code"]) {
+        let _ = value.setattr("code", code);
+    }
+    if let Ok(git) = to_py(py, &response["git"]) {
+        let _ = value.setattr("git", git);
+    }
+
+    err
+}
+
+fn is_final(response: &Value) -> bool {
+    response.get("error").is_some() || response["end"] == true
+}
+
+/// The messages of a streaming request. Dropping the iterator before it is
+/// exhausted cancels the request.
+#[pyclass(module = "giz_explorer")]
+struct ResponseIterator {
+    id: RequestId,
+    rx: Mutex<mpsc::Receiver<Value>>,
+    cancellations: CancellationRegistry,
+    finished: bool,
+    result: Value,
+}
+
+#[pymethods]
+impl ResponseIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let rx = &self.rx;
+        let response = match py.allow_threads(|| rx.lock().unwrap().recv()) {
+            Ok(response) => response,
+            Err(_) => {
+                self.finished = true;
+                return Ok(None);
+            }
+        };
+
+        if is_final(&response) {
+            self.finished = true;
+
+            if response.get("error").is_some() {
+                return Err(to_py_err(py, &response));
+            }
+
+            // the closing message only carries the stream's summary, if anything
+            self.result = response["data"].clone();
+            return Ok(None);
+        }
+
+        to_py(py, &response["data"]).map(Some)
+    }
+
+    /// The summary sent at the end of the stream (e.g. `nextCursor` of
+    /// `stream_commits`), or `None` before the stream is exhausted.
+    #[getter]
+    fn result<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py(py, &self.result)
+    }
+}
+
+impl Drop for ResponseIterator {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.cancellations.cancel(self.id);
+        }
+    }
+}
+
+/// A git repository explorer running on a background thread.
+///
+/// Results are plain dicts and lists shaped like the JSON protocol; streaming
+/// methods return iterators. Every method accepts an optional `repo` handle as
+/// returned by `open_repository`; without it the most recently opened repository
+/// is used.
+#[pyclass(module = "giz_explorer")]
+struct Explorer {
+    tx: mpsc::Sender<Job>,
+    cancellations: CancellationRegistry,
+    next_id: AtomicU64,
+}
+
+impl Explorer {
+    fn build_request(
+        &self,
+        method: &str,
+        params: Value,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Request> {
+        let request = json!({
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "repo": repo,
+            "method": method,
+            "params": params,
+        });
+
+        serde_json::from_value(request)
+            .map_err(|e| ExplorerError::new_err(explorer::ExplorerError::from(e).to_string()))
+    }
+
+    fn submit(&self, request: Request) -> PyResult<mpsc::Receiver<Value>> {
+        if let Some(id) = request.id {
+            self.cancellations.register(id);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        self.tx
+            .send((request, tx))
+            .map_err(|_| ExplorerError::new_err("the explorer has shut down"))?;
+        Ok(rx)
+    }
+
+    fn call<'py>(
+        &self,
+        py: Python<'py>,
+        method: &str,
+        params: Value,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let rx = self.submit(self.build_request(method, params, repo)?)?;
+
+        let response = py.allow_threads(move || loop {
+            match rx.recv() {
+                Ok(response) if is_final(&response) => break Some(response),
+                Ok(_) => continue,
+                Err(_) => break None,
+            }
+        });
+
+        match response {
+            Some(response) if response.get("error").is_some() => Err(to_py_err(py, &response)),
+            Some(response) => to_py(py, &response["data"]),
+            None => Err(ExplorerError::new_err("the explorer has shut down")),
+        }
+    }
+
+    fn stream(
+        &self,
+        method: &str,
+        params: Value,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<ResponseIterator> {
+        let request = self.build_request(method, params, repo)?;
+        let id = request.id.unwrap();
+
+        Ok(ResponseIterator {
+            id,
+            rx: Mutex::new(self.submit(request)?),
+            cancellations: self.cancellations.clone(),
+            finished: false,
+            result: Value::Null,
+        })
+    }
+}
+
+#[pymethods]
+impl Explorer {
+    /// Creates an explorer, opening the repository at `path` if one is given.
+    #[new]
+    #[pyo3(signature = (path=None))]
+    fn new(py: Python<'_>, path: Option<String>) -> PyResult<Self> {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let cancellations = CancellationRegistry::new();
+
+        let mut inner = explorer::Explorer::new();
+        inner.set_cancellation_registry(cancellations.clone());
+
+        std::thread::spawn(move || {
+            for (request, responses) in rx {
+                inner.handle(request, move |response| {
+                    let _ = responses.send(serde_json::to_value(response).unwrap());
+                });
+            }
+        });
+
+        let explorer = Explorer {
+            tx,
+            cancellations,
+            next_id: AtomicU64::new(0),
+        };
+
+        if let Some(path) = path {
+            explorer.open_repository(py, path, None, None)?;
+        }
+
+        Ok(explorer)
+    }
+
+    /// Opens a repository and returns its handle. Authors are mapped through the
+    /// `.mailmap` at `mailmap_rev`, or at `HEAD` if omitted, and `bot_patterns`
+    /// extend the built-in bot detection.
+    #[pyo3(signature = (path, mailmap_rev=None, bot_patterns=None))]
+    fn open_repository<'py>(
+        &self,
+        py: Python<'py>,
+        path: String,
+        mailmap_rev: Option<String>,
+        bot_patterns: Option<Vec<String>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let params = json!({
+            "path": path,
+            "mailmapRev": mailmap_rev,
+            "botPatterns": bot_patterns,
+        });
+        let result = self.call(py, "open_repository", params, None)?;
+        result.get_item("handle")
+    }
+
+    fn close_repository<'py>(
+        &self,
+        py: Python<'py>,
+        handle: RepoHandle,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.call(py, "close_repository", json!({ "handle": handle }), None)
+    }
+
+    fn list_repositories<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.call(py, "list_repositories", json!({}), None)
+    }
+
+    #[pyo3(signature = (repo=None))]
+    fn get_initial_data<'py>(
+        &self,
+        py: Python<'py>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.call(py, "get_initial_data", json!({}), repo)
+    }
+
+    #[pyo3(signature = (repo=None))]
+    fn get_branches<'py>(
+        &self,
+        py: Python<'py>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.call(py, "get_branches", json!({}), repo)
+    }
+
+    #[pyo3(signature = (
+        limit=None,
+        cursor=None,
+        order=None,
+        revs=None,
+        paths=None,
+        since=None,
+        until=None,
+        bots=None,
+        line_stats=None,
+        repo=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn get_authors<'py>(
+        &self,
+        py: Python<'py>,
+        limit: Option<u32>,
+        cursor: Option<String>,
+        order: Option<String>,
+        revs: Option<Vec<String>>,
+        paths: Option<Vec<String>>,
+        since: Option<i64>,
+        until: Option<i64>,
+        bots: Option<String>,
+        line_stats: Option<bool>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let params = json!({
+            "limit": limit,
+            "cursor": cursor,
+            "order": order,
+            "revs": revs,
+            "paths": paths,
+            "since": since,
+            "until": until,
+            "bots": bots,
+            "lineStats": line_stats,
+        });
+        self.call(py, "get_authors", params, repo)
+    }
+
+    /// Maps author ids of older id schemes to current ones; unknown ids map to `None`.
+    #[pyo3(signature = (ids, repo=None))]
+    fn map_legacy_author_ids<'py>(
+        &self,
+        py: Python<'py>,
+        ids: Vec<String>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let result = self.call(py, "map_legacy_author_ids", json!({ "ids": ids }), repo)?;
+        result.get_item("ids")
+    }
+
+    #[pyo3(signature = (
+        revs=None,
+        paths=None,
+        since=None,
+        until=None,
+        bots=None,
+        line_stats=None,
+        repo=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn stream_authors(
+        &self,
+        revs: Option<Vec<String>>,
+        paths: Option<Vec<String>>,
+        since: Option<i64>,
+        until: Option<i64>,
+        bots: Option<String>,
+        line_stats: Option<bool>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<ResponseIterator> {
+        let params = json!({
+            "revs": revs,
+            "paths": paths,
+            "since": since,
+            "until": until,
+            "bots": bots,
+            "lineStats": line_stats,
+        });
+        self.stream("stream_authors", params, repo)
+    }
+
+    #[pyo3(signature = (rev, path, preview=None, since_rev=None, bots=None, repo=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn get_blame<'py>(
+        &self,
+        py: Python<'py>,
+        rev: String,
+        path: String,
+        preview: Option<bool>,
+        since_rev: Option<String>,
+        bots: Option<String>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let params = json!({
+            "rev": rev,
+            "path": path,
+            "preview": preview,
+            "sinceRev": since_rev,
+            "bots": bots,
+        });
+        self.call(py, "get_blame", params, repo)
+    }
+
+    #[pyo3(signature = (rev, repo=None))]
+    fn get_file_tree<'py>(
+        &self,
+        py: Python<'py>,
+        rev: String,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.call(py, "get_file_tree", json!({ "rev": rev }), repo)
+    }
+
+    #[pyo3(signature = (rev, repo=None))]
+    fn stream_file_tree(
+        &self,
+        rev: String,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<ResponseIterator> {
+        self.stream("stream_file_tree", json!({ "rev": rev }), repo)
+    }
+
+    #[pyo3(signature = (rev, path, repo=None))]
+    fn get_file_content<'py>(
+        &self,
+        py: Python<'py>,
+        rev: String,
+        path: String,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.call(
+            py,
+            "get_file_content",
+            json!({ "rev": rev, "path": path }),
+            repo,
+        )
+    }
+
+    #[pyo3(signature = (
+        rev,
+        rename_threshold=None,
+        copies=None,
+        copy_threshold=None,
+        diff_mode=None,
+        parse_message=None,
+        line_stats=None,
+        repo=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn get_commit<'py>(
+        &self,
+        py: Python<'py>,
+        rev: String,
+        rename_threshold: Option<u16>,
+        copies: Option<bool>,
+        copy_threshold: Option<u16>,
+        diff_mode: Option<String>,
+        parse_message: Option<bool>,
+        line_stats: Option<bool>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let params = json!({
+            "rev": rev,
+            "renameThreshold": rename_threshold,
+            "copies": copies,
+            "copyThreshold": copy_threshold,
+            "diffMode": diff_mode,
+            "parseMessage": parse_message,
+            "lineStats": line_stats,
+        });
+        self.call(py, "get_commit", params, repo)
+    }
+
+    #[pyo3(signature = (
+        rev,
+        from_rev=None,
+        paths=None,
+        context_lines=None,
+        whitespace=None,
+        raw=None,
+        repo=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn get_diff<'py>(
+        &self,
+        py: Python<'py>,
+        rev: String,
+        from_rev: Option<String>,
+        paths: Option<Vec<String>>,
+        context_lines: Option<u32>,
+        whitespace: Option<String>,
+        raw: Option<bool>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let params = json!({
+            "rev": rev,
+            "from": from_rev,
+            "paths": paths,
+            "contextLines": context_lines,
+            "whitespace": whitespace,
+            "raw": raw,
+        });
+        self.call(py, "get_diff", params, repo)
+    }
+
+    #[pyo3(signature = (
+        limit=None,
+        cursor=None,
+        order=None,
+        revs=None,
+        authors=None,
+        paths=None,
+        since=None,
+        until=None,
+        message=None,
+        merges=None,
+        bots=None,
+        rename_threshold=None,
+        copies=None,
+        copy_threshold=None,
+        diff_mode=None,
+        parse_message=None,
+        line_stats=None,
+        repo=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn stream_commits(
+        &self,
+        limit: Option<u32>,
+        cursor: Option<String>,
+        order: Option<String>,
+        revs: Option<Vec<String>>,
+        authors: Option<Vec<String>>,
+        paths: Option<Vec<String>>,
+        since: Option<i64>,
+        until: Option<i64>,
+        message: Option<String>,
+        merges: Option<bool>,
+        bots: Option<String>,
+        rename_threshold: Option<u16>,
+        copies: Option<bool>,
+        copy_threshold: Option<u16>,
+        diff_mode: Option<String>,
+        parse_message: Option<bool>,
+        line_stats: Option<bool>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<ResponseIterator> {
+        let params = json!({
+            "limit": limit,
+            "cursor": cursor,
+            "order": order,
+            "revs": revs,
+            "authors": authors,
+            "paths": paths,
+            "since": since,
+            "until": until,
+            "message": message,
+            "merges": merges,
+            "bots": bots,
+            "renameThreshold": rename_threshold,
+            "copies": copies,
+            "copyThreshold": copy_threshold,
+            "diffMode": diff_mode,
+            "parseMessage": parse_message,
+            "lineStats": line_stats,
+        });
+        self.stream("stream_commits", params, repo)
+    }
+
+    #[pyo3(signature = (branch, repo=None))]
+    fn get_commits_for_branch<'py>(
+        &self,
+        py: Python<'py>,
+        branch: String,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.call(
+            py,
+            "get_commits_for_branch",
+            json!({ "branch": branch }),
+            repo,
+        )
+    }
+
+    #[pyo3(signature = (branch, start_seconds, end_seconds, repo=None))]
+    fn get_commits_for_time_range<'py>(
+        &self,
+        py: Python<'py>,
+        branch: String,
+        start_seconds: i64,
+        end_seconds: i64,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let params = json!({
+            "branch": branch,
+            "startSeconds": start_seconds,
+            "endSeconds": end_seconds,
+        });
+        self.call(py, "get_commits_for_time_range", params, repo)
+    }
+
+    #[pyo3(signature = (repo=None))]
+    fn get_git_graph<'py>(
+        &self,
+        py: Python<'py>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.call(py, "get_git_graph", json!({}), repo)
+    }
+
+    #[pyo3(signature = (rev, repo=None))]
+    fn is_valid_rev<'py>(
+        &self,
+        py: Python<'py>,
+        rev: String,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.call(py, "is_valid_rev", json!({ "rev": rev }), repo)
+    }
+
+    fn get_capabilities<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.call(py, "get_capabilities", json!({}), None)
+    }
+
+    /// Sends any protocol method with `params` given as a dict, for methods and
+    /// options the typed methods above do not cover. Streaming methods return an
+    /// iterator.
+    #[pyo3(signature = (method, params=None, repo=None))]
+    fn request<'py>(
+        &self,
+        py: Python<'py>,
+        method: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        repo: Option<RepoHandle>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let params = match params {
+            Some(params) => from_py(params)?,
+            None => json!({}),
+        };
+
+        let streaming = METHODS
+            .iter()
+            .any(|m| m.method == method && m.stream.is_some());
+
+        if streaming {
+            Ok(Bound::new(py, self.stream(method, params, repo)?)?.into_any())
+        } else {
+            self.call(py, method, params, repo)
+        }
+    }
+}
+
+#[pymodule]
+fn giz_explorer(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Explorer>()?;
+    m.add_class::<ResponseIterator>()?;
+    m.add("ExplorerError", m.py().get_type::<ExplorerError>())?;
+    Ok(())
+}
@@ -2,59 +2,31 @@
 #![allow(dead_code)]
 
 mod asyncify_exports;
+mod stdio;
 
-use std::io::BufRead;
+use std::sync::Arc;
 
-use explorer::{Explorer, OpenRepositoryParams, Response};
+use explorer::transport::{serve, ServeOptions};
+use explorer::{Explorer, OpenRepositoryParams};
+
+use crate::stdio::Stdio;
 
 pub fn main() {
     let mut exp = Explorer::new();
 
     exp.cmd_open_repository(&OpenRepositoryParams {
         path: "/repo".to_owned(),
+        mailmap_rev: None,
+        bot_patterns: None,
     });
 
-    while !exp.shutdown.load(std::sync::atomic::Ordering::Relaxed) {
-        let line = std::io::stdin().lock().lines().next();
-
-        if line.is_none() {
-            continue;
-        }
-
-        let line = line.unwrap();
-
-        if line.is_err() {
-            eprintln!("Failed to read from stdin: {}", line.err().unwrap());
-            continue;
-        }
-
-        let line = line.unwrap();
-
-        if line == "PING" {
-            println!("PONG");
-            continue;
-        }
-
-        let request = Explorer::deserialize_request(line.clone());
-
-        match request {
-            Ok(request) => exp.handle(request, explorer_callback),
-            Err(err) => {
-                eprintln!("Failed to deserialize request {}: {}", line, err);
-            }
-        }
-    }
-}
-
-fn explorer_callback(response: Response) {
-    let result = Explorer::serialize_response(response);
+    // There are no threads to read stdin on while a request runs, so `serve` has the
+    // running request check stdin for a `cancel` now and then.
+    let options = ServeOptions {
+        exit_on_close: false,
+    };
 
-    match result {
-        Ok(result) => {
-            println!("{}", result);
-        }
-        Err(err) => {
-            panic!("Failed to write to stdout: {}", err)
-        }
+    if let Err(err) = serve(&mut exp, Arc::new(Stdio::default()), &options) {
+        eprintln!("Failed to serve explorer: {}", err);
     }
 }
@@ -0,0 +1,95 @@
+use std::io::{self, Read, Write};
+use std::sync::Mutex;
+
+use explorer::transport::Transport;
+
+/// Newline-delimited messages over stdin and stdout. Unlike `LineTransport`, it can
+/// check stdin without waiting, so a running request picks up a `cancel`.
+#[derive(Default)]
+pub struct Stdio {
+    /// What has been read from stdin but not taken as a message yet.
+    pending: Mutex<Vec<u8>>,
+}
+
+/// Takes the first complete line out of `pending`.
+fn take_line(pending: &mut Vec<u8>) -> Option<String> {
+    let end = pending.iter().position(|byte| *byte == b'\n')?;
+    let line: Vec<u8> = pending.drain(..=end).collect();
+
+    Some(
+        String::from_utf8_lossy(&line)
+            .trim_end_matches(['\r', '\n'])
+            .to_string(),
+    )
+}
+
+/// Reads what stdin has buffered, failing with `WouldBlock` if it is empty.
+#[cfg(target_os = "wasi")]
+fn read_nonblocking(buffer: &mut [u8]) -> io::Result<usize> {
+    #[link(wasm_import_module = "wasi_snapshot_preview1")]
+    extern "C" {
+        fn fd_fdstat_set_flags(fd: i32, flags: i32) -> i32;
+    }
+
+    const STDIN: i32 = 0;
+    const FDFLAGS_NONBLOCK: i32 = 1 << 2;
+
+    let set_flags = |flags| match unsafe { fd_fdstat_set_flags(STDIN, flags) } {
+        0 => Ok(()),
+        errno => Err(io::Error::from_raw_os_error(errno)),
+    };
+
+    set_flags(FDFLAGS_NONBLOCK)?;
+    let result = io::stdin().read(buffer);
+    set_flags(0)?;
+
+    result
+}
+
+#[cfg(not(target_os = "wasi"))]
+fn read_nonblocking(_buffer: &mut [u8]) -> io::Result<usize> {
+    Err(io::ErrorKind::WouldBlock.into())
+}
+
+impl Transport for Stdio {
+    fn read_message(&self) -> io::Result<Option<String>> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut buffer = [0u8; 4096];
+
+        loop {
+            if let Some(line) = take_line(&mut pending) {
+                return Ok(Some(line));
+            }
+
+            match io::stdin().read(&mut buffer)? {
+                0 => return Ok(None),
+                read => pending.extend_from_slice(&buffer[..read]),
+            }
+        }
+    }
+
+    fn try_read_message(&self) -> io::Result<Option<String>> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut buffer = [0u8; 4096];
+
+        loop {
+            if let Some(line) = take_line(&mut pending) {
+                return Ok(Some(line));
+            }
+
+            match read_nonblocking(&mut buffer) {
+                Ok(0) => return Ok(None),
+                Ok(read) => pending.extend_from_slice(&buffer[..read]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn write_message(&self, message: &str) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(message.as_bytes())?;
+        stdout.write_all(b"\n")?;
+        stdout.flush()
+    }
+}
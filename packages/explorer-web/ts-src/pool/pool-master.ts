@@ -8,7 +8,7 @@ import { createLogger } from "@giz/logging";
 import { FSHandle } from "@giz/opfs";
 
 import { PoolNode } from "./pool-node";
-import { JobWithOrigin, PoolTask } from "./types";
+import { DataResponse, JobWithOrigin, PoolTask } from "./types";
 
 export type PoolMetrics = {
   numTotalWorkers: number;
@@ -134,8 +134,8 @@ export class PoolMaster {
         break;
       }
 
-      case "remove": {
-        this.removeJob(data.jobId);
+      case "cancel": {
+        this.cancelJob(source, data.jobId);
         break;
       }
 
@@ -149,19 +149,40 @@ export class PoolMaster {
     }
   }
 
-  removeJob(ref: JobWithOrigin | number) {
-    let index = -1;
-
-    index =
-      typeof ref === "object"
-        ? this.jobs.findIndex((j) => j.origin === ref.origin && j.id === ref.id)
-        : ref;
+  removeJob(ref: JobWithOrigin) {
+    const index = this.jobs.findIndex((j) => j.origin === ref.origin && j.id === ref.id);
 
     if (index !== -1) {
       this.jobs.splice(index, 1);
     }
   }
 
+  /**
+   * Drops a queued job, or asks the worker running it to stop.
+   * Either way the job ends with a response that has `cancelled` set.
+   */
+  cancelJob(origin: MessagePort, jobId: number) {
+    const job = this.jobs.find((j) => j.origin === origin && j.id === jobId);
+
+    if (job) {
+      this.removeJob(job);
+
+      const msg: DataResponse = {
+        id: jobId,
+        data: null,
+        end: true,
+        cancelled: true,
+      };
+      origin.postMessage(msg);
+      return;
+    }
+
+    const worker = this.busyWorkers.find(
+      (w) => w.activeJob?.origin === origin && w.activeJob.id === jobId,
+    );
+    worker?.cancel();
+  }
+
   updatePoolSize(): boolean {
     const totalWorkersCount = this.totalWorkersCount;
 
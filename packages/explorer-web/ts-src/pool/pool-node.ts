@@ -12,7 +12,8 @@ export type PoolNodeOpts = {
 };
 
 /**
- * A PoolNode represents single worker in the pool, but lives within the pool-master thread
+ * A PoolNode represents single worker in the pool, but lives within the pool-master thread.
+ * It runs one job at a time, so the pool runs as many jobs at once as it has nodes.
  */
 export class PoolNode {
   id = 0;
@@ -69,11 +70,12 @@ export class PoolNode {
     this.runtime = runtime;
   }
 
-  sendResponse(job: JobWithOrigin, data: any, end?: boolean) {
+  sendResponse(job: JobWithOrigin, data: any, end?: boolean, cancelled?: boolean) {
     const msg: DataResponse = {
       id: job.id,
       data,
       end,
+      cancelled,
     };
     job.origin.postMessage(msg);
   }
@@ -104,9 +106,10 @@ export class PoolNode {
 
     this.activeJob = job;
 
-    const { method, params } = job;
+    const { id, method, params } = job;
 
     const payload = {
+      id,
       method,
       params,
     };
@@ -117,6 +120,24 @@ export class PoolNode {
     return this.handleResponse(job);
   }
 
+  /**
+   * Asks the explorer to stop the active job. The explorer reads stdin while the job
+   * runs, and ends the job with a cancelled response once it notices.
+   */
+  cancel() {
+    if (!this.activeJob || !this.runtime) {
+      return;
+    }
+
+    // sent without an id, so its answer is told apart from the job's responses
+    const payload = {
+      method: "cancel",
+      params: { id: this.activeJob.id },
+    };
+
+    this.runtime.writeStdin(JSON.stringify(payload) + "\n");
+  }
+
   async handleResponse(job: JobWithOrigin) {
     while (this.busy) {
       const stdout = await this.runtime!.readStdout();
@@ -130,6 +151,11 @@ export class PoolNode {
         break;
       }
 
+      if (data.id !== job.id) {
+        // the answer to a `cancel`
+        continue;
+      }
+
       const isIntermediateResponse = !data.end;
       //this.logger.trace("stdout", stdout);
 
@@ -138,7 +164,7 @@ export class PoolNode {
         break;
       }
       if (!isIntermediateResponse) {
-        this.sendResponse(job, data.data, true);
+        this.sendResponse(job, data.data, true, data.cancelled);
         break;
       }
 
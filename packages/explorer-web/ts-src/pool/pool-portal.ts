@@ -128,6 +128,11 @@ export class PoolPortal {
       return;
     }
 
+    if (data.cancelled) {
+      job.onErr(new Error("Job cancelled"));
+      return;
+    }
+
     if ("data" in data) {
       job.onEnd(data.data);
     }
@@ -179,14 +184,11 @@ export class PoolPortal {
       return;
     }
 
+    // the job ends once the pool answers with a cancelled response
     this.sendTask({
-      type: "remove",
+      type: "cancel",
       jobId: job.id,
     });
-
-    job.onErr(new Error("Job cancelled"));
-
-    this.removeJob(id);
   }
 
   setJobPriority(id: number, priority: number) {
@@ -267,6 +269,10 @@ export class PoolPortal {
     return this.execute<FinalPayload<"get_commit">>("get_commit", params).promise;
   }
 
+  getDiff(params: ParameterPayloadMap["get_diff"]) {
+    return this.execute<FinalPayload<"get_diff">>("get_diff", params).promise;
+  }
+
   /**
    *
    * @deprecated
@@ -307,14 +313,28 @@ export class PoolPortal {
       onErr,
     });
   }
-  getAuthors() {
-    return this.execute<FinalPayload<"get_authors">>("get_authors", undefined, 100).promise;
+  /**
+   * Every author at once; pages are left to callers of the explorer itself.
+   */
+  getAuthors(params: Omit<ParameterPayloadMap["get_authors"], "limit" | "cursor"> = {}) {
+    return this.execute<Extract<FinalPayload<"get_authors">, any[]>>("get_authors", params, 100)
+      .promise;
+  }
+
+  mapLegacyAuthorIds(params: ParameterPayloadMap["map_legacy_author_ids"]) {
+    return this.execute<FinalPayload<"map_legacy_author_ids">>("map_legacy_author_ids", params)
+      .promise;
   }
 
-  streamAuthors(onData: (data: Author) => void, onEnd: () => void, onErr: (err: any) => void) {
+  streamAuthors(
+    onData: (data: Author) => void,
+    onEnd: () => void,
+    onErr: (err: any) => void,
+    params: ParameterPayloadMap["stream_authors"] = {},
+  ) {
     return this.stream({
       method: "stream_authors",
-      params: {},
+      params,
       onData,
       onEnd,
       onErr,
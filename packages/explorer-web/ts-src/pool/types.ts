@@ -13,6 +13,7 @@ export type DataResponse = {
   id: number;
   data: any;
   end?: boolean;
+  cancelled?: boolean;
 };
 
 export type ErrorResponse = {
@@ -34,8 +35,8 @@ export type PoolTask_UpdateJob = {
   priority: number;
 };
 
-export type PoolTask_RemoveJob = {
-  type: "remove";
+export type PoolTask_CancelJob = {
+  type: "cancel";
   jobId: number;
 };
 
@@ -46,5 +47,5 @@ export type PoolTask_ClosePort = {
 export type PoolTask =
   | PoolTask_NewJob
   | PoolTask_UpdateJob
-  | PoolTask_RemoveJob
+  | PoolTask_CancelJob
   | PoolTask_ClosePort;
@@ -1,6 +1,12 @@
-use crate::{explorer::Explorer, utils};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 
-use git2::Error;
+use crate::bots::{AuthorResolver, BotFilter, BOTS_AUTHOR_ID};
+use crate::commits::touches_paths;
+use crate::mailmap::Identity;
+use crate::{error::ExplorerResult, explorer::Explorer, message, pagination::paginate, utils};
+
+use git2::{Diff, DiffOptions};
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "bindings")]
@@ -8,7 +14,122 @@ use specta::Type;
 
 #[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, Serialize, Deserialize)]
-pub struct StreamAuthorsParams {}
+pub struct StreamAuthorsParams {
+    /// Revisions and ranges to walk, as in `stream_commits`; `HEAD` if omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revs: Option<Vec<String>>,
+
+    /// Only commits changing a file matching one of these prefixes or globs. Line
+    /// and file counts are limited to the matching files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paths: Option<Vec<String>>,
+
+    /// Only commits with an author time at or after this unix timestamp, the clock
+    /// of `firstCommitTimestamp`, `lastCommitTimestamp` and `activeDays`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+
+    /// Only commits with an author time at or before this unix timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bots: Option<BotFilter>,
+
+    /// Also count `linesAdded`, `linesRemoved` and `filesTouched`, which diffs every
+    /// commit.
+    #[serde(rename = "lineStats", skip_serializing_if = "Option::is_none")]
+    pub line_stats: Option<bool>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapLegacyAuthorIdsParams {
+    /// Ids from before `authorIdVersion`, e.g. from a stored snapshot.
+    pub ids: Vec<String>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LegacyAuthorIds {
+    /// Current id for each requested id; `null` if no author in the repository has
+    /// it. Ids that are already current map to themselves.
+    pub ids: HashMap<String, Option<String>>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorOrder {
+    /// Case-insensitive by name, then by email.
+    #[default]
+    Name,
+    /// Most commits first, then by name.
+    NumCommits,
+}
+
+impl AuthorOrder {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuthorOrder::Name => "name",
+            AuthorOrder::NumCommits => "num_commits",
+        }
+    }
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GetAuthorsParams {
+    /// Maximum number of authors to return; all remaining authors if omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+
+    /// `nextCursor` of the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<AuthorOrder>,
+
+    /// Scope of the statistics, as in `stream_authors`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revs: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paths: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bots: Option<BotFilter>,
+
+    #[serde(rename = "lineStats", skip_serializing_if = "Option::is_none")]
+    pub line_stats: Option<bool>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthorPage {
+    pub authors: Vec<Author>,
+
+    /// Cursor of the following page, or `null` on the last page.
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+/// Result of `get_authors`: every author at once, unless the request pages through
+/// them with `limit` or `cursor`.
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GetAuthorsResult {
+    Authors(Vec<Author>),
+    Page(AuthorPage),
+}
 
 #[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,84 +141,813 @@ pub struct Author {
     #[serde(rename = "gravatarHash")]
     gravatar_hash: String,
 
+    /// Matched by the built-in or configured bot patterns, as mapped by `.mailmap`;
+    /// the same for every alias.
+    #[serde(rename = "isBot")]
+    is_bot: bool,
+
+    /// Commits this author authored.
     #[serde(rename = "numCommits")]
     num_commits: u32,
+
+    /// Commits crediting this author in a `Co-authored-by` trailer.
+    #[serde(rename = "numCoAuthored")]
+    num_co_authored: u32,
+
+    /// Other identities `.mailmap` merges into this one.
+    aliases: Vec<Identity>,
+
+    /// Author time of the earliest commit this author authored; `null` if the
+    /// author was only credited as co-author.
+    #[serde(rename = "firstCommitTimestamp")]
+    first_commit_timestamp: Option<i64>,
+
+    /// Author time of the latest commit this author authored.
+    #[serde(rename = "lastCommitTimestamp")]
+    last_commit_timestamp: Option<i64>,
+
+    /// Distinct calendar days, in the author's time zone, with an authored commit.
+    #[serde(rename = "activeDays")]
+    active_days: u32,
+
+    /// Lines added by authored commits, compared to their parent. Merges do not count.
+    /// Like `linesRemoved` and `filesTouched`, only if `lineStats` was set and the
+    /// author authored a commit.
+    #[serde(rename = "linesAdded")]
+    lines_added: Option<u32>,
+
+    /// Lines removed by authored commits, compared to their parent. Merges do not count.
+    #[serde(rename = "linesRemoved")]
+    lines_removed: Option<u32>,
+
+    /// Distinct paths changed by authored commits. Merges do not count.
+    #[serde(rename = "filesTouched")]
+    files_touched: Option<u32>,
+}
+
+/// Per-author sets behind `Author::active_days` and `Author::files_touched`.
+#[derive(Default)]
+struct Activity {
+    days: HashSet<i64>,
+    paths: HashSet<String>,
+}
+
+impl Author {
+    fn new(id: String, name: String, email: String) -> Self {
+        let gravatar_hash = md5::compute(email.trim().to_lowercase());
+        Author {
+            id,
+            name,
+            email,
+            gravatar_hash: format!("{:x}", gravatar_hash),
+            is_bot: false,
+            num_commits: 0,
+            num_co_authored: 0,
+            aliases: Vec::new(),
+            first_commit_timestamp: None,
+            last_commit_timestamp: None,
+            active_days: 0,
+            lines_added: None,
+            lines_removed: None,
+            files_touched: None,
+        }
+    }
+
+    /// The author `name <email>` is credited to; `None` for excluded bots.
+    fn entry<'a>(
+        authors: &'a mut HashMap<String, Author>,
+        resolver: &AuthorResolver,
+        name: &str,
+        email: &str,
+    ) -> Option<&'a mut Author> {
+        let id = resolver.author_id(name, email)?;
+
+        Some(authors.entry(id.clone()).or_insert_with(|| {
+            let mut author = if id == BOTS_AUTHOR_ID {
+                Author::new(id, "Bots".to_string(), String::new())
+            } else {
+                let canonical = resolver.canonical(name, email);
+                Author::new(id, canonical.name, canonical.email)
+            };
+            author.is_bot = resolver.is_bot(name, email);
+            author
+        }))
+    }
+
+    fn add_commit(&mut self, activity: &mut Activity, time: git2::Time) {
+        let timestamp = time.seconds();
+        self.first_commit_timestamp = Some(
+            self.first_commit_timestamp
+                .map_or(timestamp, |first| first.min(timestamp)),
+        );
+        self.last_commit_timestamp = Some(
+            self.last_commit_timestamp
+                .map_or(timestamp, |last| last.max(timestamp)),
+        );
+
+        let local_day = (timestamp + i64::from(time.offset_minutes()) * 60).div_euclid(86400);
+        activity.days.insert(local_day);
+        self.active_days = activity.days.len() as u32;
+    }
+
+    /// Adds the changes of an authored commit to the line and file counts; `None`
+    /// for merges, which only make the counts present.
+    fn add_changes(&mut self, activity: &mut Activity, diff: Option<&Diff>) {
+        let lines_added = self.lines_added.get_or_insert(0);
+        let lines_removed = self.lines_removed.get_or_insert(0);
+        let files_touched = self.files_touched.get_or_insert(0);
+
+        let Some(diff) = diff else {
+            return;
+        };
+        if let Ok(stats) = diff.stats() {
+            *lines_added += stats.insertions() as u32;
+            *lines_removed += stats.deletions() as u32;
+        }
+        for delta in diff.deltas() {
+            let file = if delta.new_file().path().is_some() {
+                delta.new_file()
+            } else {
+                delta.old_file()
+            };
+            if let Some(path) = file.path() {
+                activity.paths.insert(path.to_string_lossy().to_string());
+            }
+        }
+        *files_touched = activity.paths.len() as u32;
+    }
+
+    fn add_alias(&mut self, name: String, email: String) {
+        let alias = Identity { name, email };
+        if (alias.name != self.name || alias.email != self.email) && !self.aliases.contains(&alias)
+        {
+            self.aliases.push(alias);
+        }
+    }
 }
 
 impl Explorer {
-    pub fn cmd_stream_authors(&self) {
-        match self.visit_authors(true) {
+    pub fn cmd_stream_authors(&self, params: &StreamAuthorsParams) {
+        match self.visit_authors(params, true) {
             Ok(_) => {}
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
 
-    pub fn cmd_get_authors(&self) {
-        match self.visit_authors(false) {
-            Ok(_) => {}
+    pub fn cmd_get_authors(&self, params: &GetAuthorsParams) {
+        match self.get_authors(params) {
+            Ok(Some(page)) => {
+                self.send(page, true);
+            }
+            Ok(None) => {
+                self.send_cancelled();
+            }
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
 
-    fn visit_authors(&self, stream: bool) -> Result<(), Error> {
-        let repo = self.repo.as_ref().unwrap();
+    pub fn cmd_map_legacy_author_ids(&self, params: &MapLegacyAuthorIdsParams) {
+        match self.map_legacy_author_ids(params) {
+            Ok(Some(ids)) => self.send(ids, true),
+            Ok(None) => self.send_cancelled(),
+            Err(e) => self.send_error(e),
+        }
+    }
+
+    /// Recomputes the legacy id of every identity in the history of all refs until
+    /// all requested ids are found. Returns `None` if the request was cancelled.
+    fn map_legacy_author_ids(
+        &self,
+        params: &MapLegacyAuthorIdsParams,
+    ) -> ExplorerResult<Option<LegacyAuthorIds>> {
+        let repo = self.repo()?;
+        let mailmap = self.mailmap()?;
+
+        let current_prefix = format!("v{}-", utils::AUTHOR_ID_VERSION);
+        let mut ids: HashMap<String, Option<String>> = params
+            .ids
+            .iter()
+            .map(|id| {
+                (
+                    id.clone(),
+                    id.starts_with(&current_prefix).then(|| id.clone()),
+                )
+            })
+            .collect();
+        let mut missing = ids.values().filter(|id| id.is_none()).count();
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_glob("*")?;
+
+        let mut seen = HashSet::new();
+        for oid in revwalk {
+            if missing == 0 {
+                break;
+            }
+            if self.is_cancelled() {
+                return Ok(None);
+            }
+
+            let commit = repo.find_commit(oid?)?;
+            let mut identities = vec![utils::signature_identity(&commit.author())];
+            identities.extend(message::co_authors(&String::from_utf8_lossy(
+                commit.message_bytes(),
+            )));
+
+            for (name, email) in identities {
+                if !seen.insert((name.clone(), email.clone())) {
+                    continue;
+                }
+
+                let canonical = mailmap.resolve(&name, &email);
+                let id = utils::get_author_id(&canonical.name, &canonical.email);
+
+                // snapshots may hold ids of either side of the mailmap
+                let legacy_ids = [
+                    utils::get_legacy_author_id(&name, &email),
+                    utils::get_legacy_author_id(&canonical.name, &canonical.email),
+                ];
+                for legacy_id in legacy_ids {
+                    if let Some(entry @ None) = ids.get_mut(&legacy_id) {
+                        *entry = Some(id.clone());
+                        missing -= 1;
+                    }
+                }
+            }
+        }
+
+        Ok(Some(LegacyAuthorIds { ids }))
+    }
+
+    /// Returns `None` if the request was cancelled.
+    fn get_authors(&self, params: &GetAuthorsParams) -> ExplorerResult<Option<GetAuthorsResult>> {
+        let scope = StreamAuthorsParams {
+            revs: params.revs.clone(),
+            paths: params.paths.clone(),
+            since: params.since,
+            until: params.until,
+            bots: params.bots,
+            line_stats: params.line_stats,
+        };
+        let authors = match self.visit_authors(&scope, false)? {
+            Some(authors) => authors,
+            None => return Ok(None),
+        };
+
+        let order = params.order.unwrap_or_default();
+
+        let mut authors: Vec<Author> = authors.into_values().collect();
+        match order {
+            AuthorOrder::Name => authors.sort_by(|a, b| {
+                (a.name.to_lowercase(), &a.email, &a.id).cmp(&(
+                    b.name.to_lowercase(),
+                    &b.email,
+                    &b.id,
+                ))
+            }),
+            AuthorOrder::NumCommits => authors.sort_by(|a, b| {
+                (Reverse(a.num_commits), a.name.to_lowercase(), &a.id).cmp(&(
+                    Reverse(b.num_commits),
+                    b.name.to_lowercase(),
+                    &b.id,
+                ))
+            }),
+        }
+
+        if params.limit.is_none() && params.cursor.is_none() {
+            return Ok(Some(GetAuthorsResult::Authors(authors)));
+        }
+
+        let (authors, next_cursor) = paginate(
+            authors,
+            |author| &author.id,
+            order.as_str(),
+            params.limit,
+            params.cursor.as_deref(),
+        )?;
+
+        Ok(Some(GetAuthorsResult::Page(AuthorPage {
+            authors,
+            next_cursor,
+        })))
+    }
+
+    /// Collects all authors of the commits selected by `params`, streaming every
+    /// update if `stream` is set. Returns `None` if the request was cancelled.
+    fn visit_authors(
+        &self,
+        params: &StreamAuthorsParams,
+        stream: bool,
+    ) -> ExplorerResult<Option<HashMap<String, Author>>> {
+        let repo = self.repo()?;
+        let resolver = self.author_resolver(params.bots.unwrap_or_default())?;
 
         let mut revwalk = repo.revwalk()?;
         revwalk.set_sorting(git2::Sort::TIME | git2::Sort::REVERSE)?;
-        revwalk.push_head()?;
+        match &params.revs {
+            Some(revs) => self.push_revs(&mut revwalk, revs)?,
+            None => revwalk.push_head()?,
+        }
+
+        let line_stats = params.line_stats.unwrap_or(false);
+        let mut diff_options = DiffOptions::new();
+        for path in params.paths.iter().flatten() {
+            diff_options.pathspec(path);
+        }
 
-        let mut authors = std::collections::HashMap::new();
+        let mut authors = HashMap::new();
+        let mut activities: HashMap<String, Activity> = HashMap::new();
 
         for oid in revwalk {
+            if self.is_cancelled() {
+                if stream {
+                    self.send_cancelled();
+                }
+                return Ok(None);
+            }
+
             let oid = oid?;
             let commit = repo.find_commit(oid)?;
-            let author = commit.author();
-            let author_email = author.email();
-            if author_email.is_none() {
+
+            let timestamp = commit.author().when().seconds();
+            if params.since.is_some_and(|since| timestamp < since)
+                || params.until.is_some_and(|until| timestamp > until)
+            {
                 continue;
             }
-            let author_name = author.name();
-            if author_name.is_none() {
-                continue;
+
+            // merges would credit their author with everything merged in
+            let diff = if !line_stats || commit.parent_count() > 1 {
+                None
+            } else {
+                let parent_tree = match commit.parents().next() {
+                    Some(parent) => Some(parent.tree()?),
+                    None => None,
+                };
+                Some(repo.diff_tree_to_tree(
+                    parent_tree.as_ref(),
+                    Some(&commit.tree()?),
+                    Some(&mut diff_options),
+                )?)
+            };
+
+            if let Some(paths) = &params.paths {
+                let touched = match &diff {
+                    Some(diff) => diff.deltas().len() > 0,
+                    None => touches_paths(repo, &commit, paths)?,
+                };
+                if !touched {
+                    continue;
+                }
             }
 
-            let author_name = author_name.unwrap().to_string();
-            let author_email = author_email.unwrap().to_string();
+            let (author_name, author_email) = utils::signature_identity(&commit.author());
+
+            let mut credited = Vec::new();
+
+            if let Some(author) =
+                Author::entry(&mut authors, &resolver, &author_name, &author_email)
+            {
+                author.num_commits += 1;
+                author.add_alias(author_name, author_email);
 
-            let gravatar_hash = md5::compute(author_email.clone().trim().to_lowercase());
+                let activity = activities.entry(author.id.clone()).or_default();
+                author.add_commit(activity, commit.author().when());
+                if line_stats {
+                    author.add_changes(activity, diff.as_ref());
+                }
+                credited.push(author.id.clone());
+
+                if stream {
+                    self.send(author, false);
+                }
+            }
 
-            let author_id = utils::get_author_id(&author_name, &author_email);
+            let commit_message = String::from_utf8_lossy(commit.message_bytes());
 
-            let author = authors.entry(author_id.clone()).or_insert(Author {
-                id: author_id.clone(),
-                name: author_name.clone(),
-                email: author_email.clone(),
-                gravatar_hash: format!("{:x}", gravatar_hash),
-                num_commits: 0,
-            });
+            for (name, email) in message::co_authors(&commit_message) {
+                let Some(co_author) = Author::entry(&mut authors, &resolver, &name, &email) else {
+                    continue;
+                };
+                if credited.contains(&co_author.id) {
+                    continue;
+                }
 
-            author.num_commits += 1;
+                co_author.num_co_authored += 1;
+                co_author.add_alias(name, email);
+                credited.push(co_author.id.clone());
 
-            if stream {
-                self.send(author, false);
+                if stream {
+                    self.send(co_author, false);
+                }
             }
         }
 
         if stream {
             self.send((), true);
-        } else {
-            let mut result = Vec::new();
+        }
+
+        Ok(Some(authors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
 
-            for (_, author) in authors.iter() {
-                result.push(author);
+    use serde_json::Value;
+
+    use super::{GetAuthorsParams, MapLegacyAuthorIdsParams};
+    use crate::bots::BotFilter;
+    use crate::explorer::Explorer;
+    use crate::test_repo::TestRepo;
+    use crate::utils;
+
+    const DAY: i64 = 86400;
+    /// Midnight UTC, so that `DAY` steps land on separate days.
+    const T: i64 = 1_699_920_000;
+
+    /// Every author at once, as JSON.
+    fn authors(explorer: &Explorer, params: GetAuthorsParams) -> Vec<Value> {
+        let authors = explorer.get_authors(&params).unwrap().unwrap();
+        match serde_json::to_value(authors).unwrap() {
+            Value::Array(authors) => authors,
+            page => panic!("expected every author, got {}", page),
+        }
+    }
+
+    /// `key` of every author, by name.
+    fn field(authors: &[Value], key: &str) -> Vec<(String, Value)> {
+        let field = |author: &Value| {
+            (
+                author["name"].as_str().unwrap().to_string(),
+                author[key].clone(),
+            )
+        };
+        authors.iter().map(field).collect()
+    }
+
+    #[test]
+    fn maps_legacy_ids_to_current_ones() {
+        // `.mailmap` maps `alice <alice@old.org>` to `Alice Liddell <alice@x.org>`
+        let repo = TestRepo::new("legacy-ids");
+        repo.commit("add mailmap\n\nCo-authored-by: Bob <bob@x.org>\n")
+            .author("alice", "alice@old.org")
+            .file(".mailmap", "Alice Liddell <alice@x.org> <alice@old.org>\n")
+            .create();
+        let explorer = repo.explorer();
+
+        let alice = "v1-581bde9c4a1b5229".to_string();
+        let bob = "v1-b100fad6669e4c4d".to_string();
+        let ids = vec![
+            // either side of the mailmap maps to the canonical id
+            utils::get_legacy_author_id("alice", "alice@old.org"),
+            utils::get_legacy_author_id("Alice Liddell", "alice@x.org"),
+            utils::get_legacy_author_id("Bob", "bob@x.org"),
+            "0123456789abcdef".to_string(),
+            // current ids are passed through
+            bob.clone(),
+        ];
+
+        let mapped = explorer
+            .map_legacy_author_ids(&MapLegacyAuthorIdsParams { ids: ids.clone() })
+            .unwrap()
+            .unwrap();
+
+        let expected = HashMap::from([
+            (ids[0].clone(), Some(alice.clone())),
+            (ids[1].clone(), Some(alice)),
+            (ids[2].clone(), Some(bob.clone())),
+            (ids[3].clone(), None),
+            (bob.clone(), Some(bob)),
+        ]);
+        assert_eq!(mapped.ids, expected);
+    }
+
+    #[test]
+    fn pages_authors_only_when_asked_to() {
+        let repo = TestRepo::new("authors-pages");
+        repo.commit("one").file("a.txt", "1").create();
+        repo.commit("two")
+            .author("Bob", "bob@x.org")
+            .file("a.txt", "2")
+            .create();
+        let explorer = repo.explorer();
+
+        let all = explorer.get_authors(&GetAuthorsParams::default()).unwrap();
+        let all = serde_json::to_value(all.unwrap()).unwrap();
+        let names: Vec<_> = all.as_array().unwrap().iter().map(|a| &a["name"]).collect();
+        assert_eq!(names, ["Alice", "Bob"]);
+
+        let params = GetAuthorsParams {
+            limit: Some(1),
+            ..Default::default()
+        };
+        let page = explorer.get_authors(&params).unwrap();
+        let page = serde_json::to_value(page.unwrap()).unwrap();
+        assert_eq!(page["authors"].as_array().unwrap().len(), 1);
+        assert_eq!(page["authors"][0]["name"], "Alice");
+        assert!(page["nextCursor"].is_string());
+    }
+
+    #[test]
+    fn counts_authors_whose_names_are_not_utf8() {
+        let repo = TestRepo::new("authors-latin1");
+        repo.commit("one")
+            .author_bytes(b"Ren\xe9", "rene@x.org")
+            .file("a.txt", "1")
+            .create();
+        let explorer = repo.explorer();
+
+        let all = explorer.get_authors(&GetAuthorsParams::default()).unwrap();
+        let all = serde_json::to_value(all.unwrap()).unwrap();
+        assert_eq!(all[0]["name"], "Ren\u{fffd}");
+        // the id of the name with the invalid byte replaced
+        assert_eq!(all[0]["id"], "v1-be44ffc180de2aa9");
+    }
+
+    /// Alice and Bob on `main`, with a merge of Bob's `side` branch, one day apart:
+    ///
+    /// 0. Alice adds `src/a.rs` (3 lines) and `README` (1 line)
+    /// 1. Bob edits a line of `src/a.rs`, co-authored by Alice; an hour later,
+    ///    Alice adds `docs/b.md` (2 lines)
+    /// 2. Bob adds `src/c.rs` (1 line) on `side`, which starts at day 0
+    /// 3. Alice merges `side`
+    fn history(name: &str) -> TestRepo {
+        let repo = TestRepo::new(name);
+        let root = repo
+            .commit("one")
+            .time(T)
+            .file("src/a.rs", "a\nb\nc\n")
+            .file("README", "hi\n")
+            .create();
+        repo.commit("two\n\nCo-authored-by: Alice <alice@x.org>\n")
+            .author("Bob", "bob@x.org")
+            .time(T + DAY)
+            .file("src/a.rs", "a\nB\nc\n")
+            .create();
+        let main = repo
+            .commit("three")
+            .time(T + DAY + 3600)
+            .file("docs/b.md", "1\n2\n")
+            .create();
+        let side = repo
+            .commit("four")
+            .author("Bob", "bob@x.org")
+            .time(T + 2 * DAY)
+            .on("refs/heads/side")
+            .parents(&[root])
+            .file("src/c.rs", "c\n")
+            .create();
+        repo.commit("merge")
+            .time(T + 3 * DAY)
+            .parents(&[main, side])
+            .file("src/c.rs", "c\n")
+            .create();
+        repo
+    }
+
+    #[test]
+    fn counts_commits_and_days() {
+        let repo = history("authors-days");
+        let all = authors(&repo.explorer(), GetAuthorsParams::default());
+
+        let alice = "Alice".to_string();
+        let bob = "Bob".to_string();
+        assert_eq!(
+            field(&all, "numCommits"),
+            [(alice.clone(), 3.into()), (bob.clone(), 2.into())]
+        );
+        assert_eq!(
+            field(&all, "numCoAuthored"),
+            [(alice.clone(), 1.into()), (bob.clone(), 0.into())]
+        );
+        assert_eq!(
+            field(&all, "firstCommitTimestamp"),
+            [(alice.clone(), T.into()), (bob.clone(), (T + DAY).into())]
+        );
+        assert_eq!(
+            field(&all, "lastCommitTimestamp"),
+            [
+                (alice.clone(), (T + 3 * DAY).into()),
+                (bob.clone(), (T + 2 * DAY).into())
+            ]
+        );
+        assert_eq!(
+            field(&all, "activeDays"),
+            [(alice.clone(), 3.into()), (bob.clone(), 2.into())]
+        );
+        // only counted with `lineStats`
+        assert_eq!(
+            field(&all, "linesAdded"),
+            [(alice, Value::Null), (bob, Value::Null)]
+        );
+    }
+
+    #[test]
+    fn counts_lines_and_files_without_merges() {
+        let repo = history("authors-lines");
+        let explorer = repo.explorer();
+        let line_stats = || GetAuthorsParams {
+            line_stats: Some(true),
+            ..Default::default()
+        };
+
+        let all = authors(&explorer, line_stats());
+        let counts = |authors: &[Value]| -> Vec<(String, [Value; 3])> {
+            let counts = |author: &Value| {
+                (
+                    author["name"].as_str().unwrap().to_string(),
+                    [
+                        author["linesAdded"].clone(),
+                        author["linesRemoved"].clone(),
+                        author["filesTouched"].clone(),
+                    ],
+                )
+            };
+            authors.iter().map(counts).collect()
+        };
+        assert_eq!(
+            counts(&all),
+            [
+                ("Alice".to_string(), [6.into(), 0.into(), 3.into()]),
+                ("Bob".to_string(), [2.into(), 1.into(), 2.into()]),
+            ]
+        );
+
+        // paths limit both the commits and what is counted of them
+        let src = authors(
+            &explorer,
+            GetAuthorsParams {
+                paths: Some(vec!["src".to_string()]),
+                ..line_stats()
+            },
+        );
+        assert_eq!(
+            field(&src, "numCommits"),
+            [
+                ("Alice".to_string(), 2.into()),
+                ("Bob".to_string(), 2.into())
+            ]
+        );
+        assert_eq!(
+            counts(&src),
+            [
+                ("Alice".to_string(), [3.into(), 0.into(), 1.into()]),
+                ("Bob".to_string(), [2.into(), 1.into(), 2.into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn scopes_authors_to_revs() {
+        let repo = history("authors-revs");
+        let explorer = repo.explorer();
+        let revs = |revs: &[&str]| {
+            let params = GetAuthorsParams {
+                revs: Some(revs.iter().map(|rev| rev.to_string()).collect()),
+                ..Default::default()
+            };
+            field(&authors(&explorer, params), "numCommits")
+        };
+
+        assert_eq!(
+            revs(&["side"]),
+            [
+                ("Alice".to_string(), 1.into()),
+                ("Bob".to_string(), 1.into())
+            ]
+        );
+        assert_eq!(
+            revs(&["side..main"]),
+            [
+                ("Alice".to_string(), 2.into()),
+                ("Bob".to_string(), 1.into())
+            ]
+        );
+    }
+
+    #[test]
+    fn windows_authors_by_author_time() {
+        let repo = history("authors-window");
+        let explorer = repo.explorer();
+        let window = |since, until| {
+            let params = GetAuthorsParams {
+                since,
+                until,
+                ..Default::default()
+            };
+            authors(&explorer, params)
+        };
+
+        let days = window(Some(T + DAY), Some(T + 2 * DAY));
+        assert_eq!(
+            field(&days, "numCommits"),
+            [
+                ("Alice".to_string(), 1.into()),
+                ("Bob".to_string(), 2.into())
+            ]
+        );
+        // co-authorship counts within the window too
+        assert_eq!(
+            field(&days, "numCoAuthored")[0],
+            ("Alice".to_string(), 1.into())
+        );
+
+        let since = window(Some(T + 3 * DAY), None);
+        assert_eq!(
+            field(&since, "numCommits"),
+            [("Alice".to_string(), 1.into())]
+        );
+        let until = window(None, Some(T));
+        assert_eq!(
+            field(&until, "numCommits"),
+            [("Alice".to_string(), 1.into())]
+        );
+
+        // commits rebased or applied later keep their author time
+        let repo = TestRepo::new("authors-rebased");
+        repo.commit("old")
+            .time(T)
+            .committed_at(T + 10 * DAY)
+            .file("a.txt", "1")
+            .create();
+        repo.commit("new")
+            .author("Bob", "bob@x.org")
+            .time(T + 10 * DAY)
+            .file("a.txt", "2")
+            .create();
+        let explorer = repo.explorer();
+        let params = GetAuthorsParams {
+            until: Some(T + DAY),
+            ..Default::default()
+        };
+        let early = authors(&explorer, params);
+        assert_eq!(
+            field(&early, "numCommits"),
+            [("Alice".to_string(), 1.into())]
+        );
+        assert_eq!(field(&early, "lastCommitTimestamp")[0].1, T);
+    }
+
+    #[test]
+    fn classifies_bots_by_their_mapped_identity() {
+        let alice = ("Alice", "alice@x.org");
+        // a bot-like alias of a human, and a human-like alias of a bot
+        let deploy = ("deploy[bot]", "deploy@x.org");
+        let ci = ("ci", "ci@x.org");
+        let build = ("build[bot]", "build@x.org");
+        let mailmap = "Alice <alice@x.org> <deploy@x.org>\nbuild[bot] <build@x.org> <ci@x.org>\n";
+
+        let orders = [
+            ("bots-forwards", [alice, deploy, ci, build]),
+            ("bots-backwards", [build, ci, deploy, alice]),
+        ];
+        for (name, order) in orders {
+            let repo = TestRepo::new(name);
+            for (name, email) in order {
+                repo.commit(name)
+                    .author(name, email)
+                    .file(".mailmap", mailmap)
+                    .file("a.txt", name)
+                    .create();
             }
+            let explorer = repo.explorer();
+            let with_bots = |bots| {
+                let params = GetAuthorsParams {
+                    bots: Some(bots),
+                    ..Default::default()
+                };
+                authors(&explorer, params)
+            };
+
+            let all = with_bots(BotFilter::Include);
+            assert_eq!(
+                field(&all, "isBot"),
+                [
+                    ("Alice".to_string(), false.into()),
+                    ("build[bot]".to_string(), true.into())
+                ]
+            );
+
+            let excluded = with_bots(BotFilter::Exclude);
+            assert_eq!(
+                field(&excluded, "numCommits"),
+                [("Alice".to_string(), 2.into())]
+            );
 
-            self.send(result, true);
+            let separate = with_bots(BotFilter::Separate);
+            assert_eq!(
+                field(&separate, "numCommits"),
+                [
+                    ("Alice".to_string(), 2.into()),
+                    ("Bots".to_string(), 2.into())
+                ]
+            );
         }
-        Ok(())
     }
 }
@@ -1,7 +1,9 @@
-use crate::{explorer::Explorer, utils};
+use crate::error::{ExplorerError, ExplorerResult};
+use crate::bots::BotFilter;
+use crate::{explorer::Explorer, message};
 use git2::BlameOptions;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
@@ -15,6 +17,20 @@ pub struct Blame {
     file_name: String,
     commits: HashMap<String, CommitInfo>,
     lines: Vec<BlameLine>,
+
+    /// Lines per author id, including co-authors.
+    ownership: HashMap<String, Ownership>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Ownership {
+    /// Lines last changed by a commit the author authored.
+    lines: u32,
+
+    /// Lines last changed by a commit crediting the author as co-author.
+    #[serde(rename = "coAuthoredLines")]
+    co_authored_lines: u32,
 }
 
 #[cfg_attr(feature = "bindings", derive(Type))]
@@ -34,6 +50,11 @@ struct CommitInfo {
     commit_id: String,
     #[serde(rename = "authorId")]
     author_id: String,
+
+    /// Authors credited in `Co-authored-by` trailers.
+    #[serde(rename = "coAuthorIds")]
+    co_author_ids: Vec<String>,
+
     timestamp: String,
 }
 
@@ -47,6 +68,11 @@ pub struct BlameParams {
 
     #[serde(skip_serializing_if = "Option::is_none", rename = "sinceRev")]
     pub since_rev: Option<String>,
+
+    /// `exclude` leaves lines of bot commits out of `ownership`, `separate`
+    /// attributes them to the author id `bots`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bots: Option<BotFilter>,
     
 }
 
@@ -57,19 +83,24 @@ impl Explorer {
                 self.send(blame, true);
             }
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
 
-    fn blame(&self, params: &BlameParams) -> Result<Blame, git2::Error> {
-        let repo = self.repo.as_ref().unwrap();
+    fn blame(&self, params: &BlameParams) -> ExplorerResult<Blame> {
+        let repo = self.repo()?;
+        let mailmap = self.mailmap()?;
+        let resolver = self.author_resolver(params.bots.unwrap_or_default())?;
+        // commits of excluded bots, which own no lines
+        let mut excluded = HashSet::new();
 
-        let file_name = params.path.split('/').last().unwrap();
+        let file_name = params.path.split('/').last().unwrap_or_default();
         let mut result = Blame {
             file_name: file_name.to_string(),
             commits: HashMap::new(),
             lines: Vec::new(),
+            ownership: HashMap::new(),
         };
 
         let path = Path::new(params.path.as_str());
@@ -92,17 +123,22 @@ impl Explorer {
             .track_copies_same_file(true);
 
         if let Some(true) = params.preview {
-            opts = opts
-                .first_parent(true)
-                .oldest_commit(commit.parent(0).unwrap().id());
+            opts = opts.first_parent(true).oldest_commit(commit.parent_id(0)?);
         } else if let Some(since_rev) = &params.since_rev {
-            let since_commit_id = repo.revparse_single(since_rev.as_str())?.id();
+            let since_commit_id = repo
+                .revparse_single(since_rev.as_str())
+                .map_err(|e| ExplorerError::from_rev_lookup(since_rev, e))?
+                .id();
             opts = opts.oldest_commit(since_commit_id);
         }
 
-        let blame = repo.blame_file(path, Some(&mut opts))?;
+        let blame = repo
+            .blame_file(path, Some(&mut opts))
+            .map_err(|e| ExplorerError::from_path_lookup(&params.path, e))?;
 
-        let object = repo.revparse_single(&spec[..])?;
+        let object = repo
+            .revparse_single(&spec[..])
+            .map_err(|e| ExplorerError::from_path_lookup(&params.path, e))?;
         let blob = repo.find_blob(object.id())?;
         let reader = BufReader::new(blob.content());
 
@@ -121,18 +157,54 @@ impl Explorer {
                 let author_name = String::from_utf8_lossy(sig.name_bytes()).to_string();
                 let author_email = String::from_utf8_lossy(sig.email_bytes()).to_string();
 
-                let author_id = utils::get_author_id(&author_name, &author_email);
-
                 if !result.commits.contains_key(commit_id.clone().as_str()) {
+                    let author_id = match resolver.author_id(&author_name, &author_email) {
+                        Some(author_id) => author_id,
+                        None => {
+                            excluded.insert(commit_id.clone());
+                            mailmap.author_id(&author_name, &author_email)
+                        }
+                    };
+
+                    let final_commit = repo.find_commit(hunk.final_commit_id())?;
+                    let commit_message = String::from_utf8_lossy(final_commit.message_bytes());
+
+                    let mut co_author_ids: Vec<String> = Vec::new();
+                    for (name, email) in message::co_authors(&commit_message) {
+                        let Some(co_author_id) = resolver.author_id(&name, &email) else {
+                            continue;
+                        };
+                        if co_author_id != author_id && !co_author_ids.contains(&co_author_id) {
+                            co_author_ids.push(co_author_id);
+                        }
+                    }
+
                     result.commits.insert(
                         commit_id.clone(),
                         CommitInfo {
                             commit_id: commit_id.to_string(),
                             author_id,
+                            co_author_ids,
                             timestamp: sig.when().seconds().to_string(),
                         },
                     );
                 }
+
+                let info = &result.commits[&commit_id];
+                if !excluded.contains(&commit_id) {
+                    result
+                        .ownership
+                        .entry(info.author_id.clone())
+                        .or_default()
+                        .lines += 1;
+                }
+                for co_author_id in &info.co_author_ids {
+                    result
+                        .ownership
+                        .entry(co_author_id.clone())
+                        .or_default()
+                        .co_authored_lines += 1;
+                }
             }
         }
         Ok(result)
@@ -0,0 +1,115 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bindings")]
+use specta::Type;
+
+use crate::error::{ExplorerError, ExplorerResult};
+use crate::explorer::Explorer;
+use crate::mailmap::{Identity, Mailmap};
+
+/// Author id shared by all bots when their activity is aggregated separately.
+pub const BOTS_AUTHOR_ID: &str = "bots";
+
+/// Matched case-insensitively against author names and emails, next to the
+/// `botPatterns` given to `open_repository`.
+const BUILTIN_PATTERNS: &[&str] = &[
+    // GitHub apps, e.g. `dependabot[bot] <49699333+dependabot[bot]@users.noreply.github.com>`
+    r"\[bot\]$",
+    r"\[bot\]@",
+    r"^(dependabot|renovate|greenkeeper|snyk-bot|semantic-release-bot|mergify|pre-commit-ci|imgbot|allcontributors|depfu|github-actions)\b",
+    r"^action@github\.com$",
+];
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotFilter {
+    /// Bots are treated like everyone else.
+    #[default]
+    Include,
+    /// Bots and their commits are left out.
+    Exclude,
+    /// All bots are merged into a single author with the id `bots`.
+    Separate,
+}
+
+/// Decides which authors are bots.
+#[derive(Debug)]
+pub struct BotDetector {
+    patterns: Vec<Regex>,
+}
+
+impl Default for BotDetector {
+    fn default() -> Self {
+        BotDetector::new(&[]).unwrap()
+    }
+}
+
+impl BotDetector {
+    /// The built-in patterns plus `extra`, which are regular expressions.
+    pub fn new(extra: &[String]) -> ExplorerResult<BotDetector> {
+        let builtin = BUILTIN_PATTERNS.iter().copied();
+        let patterns = builtin
+            .chain(extra.iter().map(String::as_str))
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| {
+                        ExplorerError::InvalidParams(format!("invalid bot pattern: {}", e))
+                    })
+            })
+            .collect::<ExplorerResult<_>>()?;
+
+        Ok(BotDetector { patterns })
+    }
+
+    pub fn is_bot(&self, name: &str, email: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.is_match(name) || pattern.is_match(email))
+    }
+}
+
+/// Derives author ids through the `.mailmap` and bot filter of a repository.
+pub struct AuthorResolver<'a> {
+    mailmap: &'a Mailmap,
+    bots: &'a BotDetector,
+    filter: BotFilter,
+}
+
+impl AuthorResolver<'_> {
+    /// The identity as mapped by `.mailmap`.
+    pub fn canonical(&self, name: &str, email: &str) -> Identity {
+        self.mailmap.resolve(name, email)
+    }
+
+    /// Whether the identity as mapped by `.mailmap` is a bot, so that all aliases of
+    /// an author are classified, and filtered, alike.
+    pub fn is_bot(&self, name: &str, email: &str) -> bool {
+        let canonical = self.mailmap.resolve(name, email);
+        self.bots.is_bot(&canonical.name, &canonical.email)
+    }
+
+    /// The author id to report under the filter; `None` for excluded bots.
+    pub fn author_id(&self, name: &str, email: &str) -> Option<String> {
+        if self.filter != BotFilter::Include && self.is_bot(name, email) {
+            return match self.filter {
+                BotFilter::Separate => Some(BOTS_AUTHOR_ID.to_string()),
+                _ => None,
+            };
+        }
+        Some(self.mailmap.author_id(name, email))
+    }
+}
+
+impl Explorer {
+    pub(crate) fn author_resolver(&self, filter: BotFilter) -> ExplorerResult<AuthorResolver<'_>> {
+        Ok(AuthorResolver {
+            mailmap: self.mailmap()?,
+            bots: self.repositories.get_bots(self.request_repo)?,
+            filter,
+        })
+    }
+}
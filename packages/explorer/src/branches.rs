@@ -1,9 +1,10 @@
-use git2::{BranchType, Error};
+use git2::BranchType;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "bindings")]
 use specta::Type;
 
+use crate::error::{ExplorerError, ExplorerResult};
 use crate::explorer::Explorer;
 
 #[cfg_attr(feature = "bindings", derive(Type))]
@@ -28,23 +29,24 @@ impl Explorer {
                 self.send(branches, true);
             }
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
 
-    fn get_branches(&self) -> Result<Vec<String>, Error> {
-        let repo = self.repo.as_ref().unwrap();
+    fn get_branches(&self) -> ExplorerResult<Vec<String>> {
+        let repo = self.repo()?;
 
         let mut result: Vec<String> = Vec::new();
 
         let branches = repo.branches(Some(BranchType::Local))?;
 
-        branches.for_each(|branch| {
-            let (branch, _) = branch.unwrap();
-            let name = branch.name().unwrap().unwrap();
-            result.push(name.to_string());
-        });
+        for branch in branches {
+            let (branch, _) = branch?;
+            if let Some(name) = branch.name()? {
+                result.push(name.to_string());
+            }
+        }
         Ok(result)
     }
 
@@ -54,7 +56,7 @@ impl Explorer {
                 self.send(commits, true);
             }
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
@@ -62,21 +64,24 @@ impl Explorer {
     fn get_commits_for_branch(
         &self,
         params: &GetCommitsForBranchParams,
-    ) -> Result<CommitsForBranch, Error> {
-        let repo = self.repo.as_ref().unwrap();
-
+    ) -> ExplorerResult<CommitsForBranch> {
+        let repo = self.repo()?;
 
         let end_commit = repo
-            .find_branch(&params.branch, BranchType::Local)?
+            .find_branch(&params.branch, BranchType::Local)
+            .map_err(|e| ExplorerError::from_rev_lookup(&params.branch, e))?
             .into_reference()
             .peel_to_commit()?;
 
         // find the first commit of the branch
         let mut revwalk = repo.revwalk()?;
-        revwalk.push(end_commit.id()).unwrap();
+        revwalk.push(end_commit.id())?;
         revwalk.set_sorting(git2::Sort::TIME | git2::Sort::REVERSE)?;
 
-        let first_commit_id = revwalk.next().unwrap().unwrap();
+        let first_commit_id = match revwalk.next() {
+            Some(oid) => oid?,
+            None => return Err(ExplorerError::RevNotFound(params.branch.clone())),
+        };
 
         Ok(CommitsForBranch {
             start_commit: first_commit_id.to_string(),
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bindings")]
+use specta::Type;
+
+use crate::explorer::{Explorer, RequestId};
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CancelParams {
+    pub id: RequestId,
+}
+
+#[derive(Debug, Default)]
+struct Requests {
+    /// Requests that are queued or running.
+    active: HashSet<RequestId>,
+
+    /// The subset of `active` that has been asked to stop.
+    cancelled: HashSet<RequestId>,
+}
+
+/// Tracks which requests are in flight and which of them have been asked to stop.
+///
+/// The registry is shared between clones, so a host can keep a handle to it and
+/// cancel requests from another thread while the explorer is busy. Hosts that queue
+/// requests register them when queuing, so they can be cancelled before they start.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationRegistry {
+    requests: Arc<Mutex<Requests>>,
+}
+
+impl CancellationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks a request as queued or running, so that it can be cancelled.
+    pub fn register(&self, id: RequestId) {
+        self.requests.lock().unwrap().active.insert(id);
+    }
+
+    /// Asks a queued or running request to stop. Returns `false`, and remembers
+    /// nothing, if no such request is in flight.
+    pub fn cancel(&self, id: RequestId) -> bool {
+        let mut requests = self.requests.lock().unwrap();
+        if !requests.active.contains(&id) {
+            return false;
+        }
+        requests.cancelled.insert(id);
+        true
+    }
+
+    pub fn is_cancelled(&self, id: RequestId) -> bool {
+        self.requests.lock().unwrap().cancelled.contains(&id)
+    }
+
+    /// Forgets a request once it has finished.
+    pub fn clear(&self, id: RequestId) {
+        let mut requests = self.requests.lock().unwrap();
+        requests.active.remove(&id);
+        requests.cancelled.remove(&id);
+    }
+}
+
+/// How often a running request reads its transport for a `cancel` where `serve`
+/// cannot read it on a thread of its own.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Reads the messages that arrived while a request runs, so that a `cancel` among
+/// them is applied. Runs from `Explorer::is_cancelled`, at most every `POLL_INTERVAL`.
+pub(crate) struct CancellationPoll {
+    poll: Box<dyn Fn() + Send + Sync>,
+    last: Mutex<Option<Instant>>,
+}
+
+impl CancellationPoll {
+    pub(crate) fn new(poll: impl Fn() + Send + Sync + 'static) -> Self {
+        CancellationPoll {
+            poll: Box::new(poll),
+            last: Mutex::new(None),
+        }
+    }
+
+    fn run(&self) {
+        {
+            let mut last = self.last.lock().unwrap();
+            if last.is_some_and(|last| last.elapsed() < POLL_INTERVAL) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+
+        (self.poll)();
+    }
+}
+
+impl Explorer {
+    pub fn cancellation_registry(&self) -> CancellationRegistry {
+        self.cancellations.clone()
+    }
+
+    /// Makes this explorer observe a registry shared with other explorers, such as
+    /// the workers of a pool, so one `cancel` reaches whichever of them runs the request.
+    pub fn set_cancellation_registry(&mut self, registry: CancellationRegistry) {
+        self.cancellations = registry;
+    }
+
+    /// Whether the request currently being handled has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        if let Some(poll) = &self.cancellation_poll {
+            poll.run();
+        }
+
+        match self.request_id {
+            Some(id) => self.cancellations.is_cancelled(id),
+            None => false,
+        }
+    }
+
+    /// Answers whether a request with that id was in flight.
+    pub fn cmd_cancel(&self, params: &CancelParams) {
+        let cancelled = self.cancellations.cancel(params.id);
+        self.send(cancelled, true);
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bindings")]
+use specta::Type;
+
+use crate::explorer::Explorer;
+use crate::protocol::METHODS;
+use crate::utils::AUTHOR_ID_VERSION;
+
+/// Version of the request/response protocol. Bumped on breaking changes only;
+/// additive changes are discoverable through `methods` and `features`.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Limits {
+    /// Number of requests the host executes at the same time, e.g. the size of its
+    /// worker pool.
+    #[serde(rename = "concurrentRequests")]
+    pub concurrent_requests: u32,
+
+    /// Maximum length of commit messages in `get_git_graph` results.
+    #[serde(rename = "graphMessageLength")]
+    pub graph_message_length: u32,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Capabilities {
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: u32,
+
+    #[serde(rename = "crateVersion")]
+    pub crate_version: String,
+
+    /// Scheme of author ids; ids of older schemes can be translated with
+    /// `map_legacy_author_ids`.
+    #[serde(rename = "authorIdVersion")]
+    pub author_id_version: u32,
+
+    pub methods: Vec<String>,
+    pub features: Vec<String>,
+    pub limits: Limits,
+}
+
+pub fn get_capabilities(concurrent_requests: u32) -> Capabilities {
+    let mut features = Vec::new();
+
+    if cfg!(feature = "bindings") {
+        features.push("bindings".to_string());
+    }
+    // petgraph backs `get_git_graph` and is compiled in on every target
+    features.push("petgraph".to_string());
+    if cfg!(panic = "unwind") {
+        features.push("panic_isolation".to_string());
+    }
+
+    Capabilities {
+        protocol_version: PROTOCOL_VERSION,
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        author_id_version: AUTHOR_ID_VERSION,
+        methods: METHODS.iter().map(|m| m.method.to_string()).collect(),
+        features,
+        limits: Limits {
+            concurrent_requests,
+            graph_message_length: 120,
+        },
+    }
+}
+
+impl Explorer {
+    /// Sets the number of requests the host runs at the same time, as reported by
+    /// `get_capabilities`. Defaults to one.
+    pub fn set_concurrent_requests(&mut self, concurrent_requests: u32) {
+        self.concurrent_requests = concurrent_requests;
+    }
+
+    pub fn cmd_get_capabilities(&self) {
+        self.send(get_capabilities(self.concurrent_requests), true);
+    }
+}
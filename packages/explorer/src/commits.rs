@@ -1,15 +1,431 @@
+use crate::bots::{AuthorResolver, BotFilter};
+use crate::error::{ExplorerError, ExplorerResult};
 use crate::explorer::Explorer;
 use crate::git_graph::to_string_oid;
-use crate::utils::get_author_id;
-use git2::{Error, Oid};
+use crate::message::{parse_message, ParsedMessage};
+use crate::pagination::{validate_limit, Cursor};
+use crate::utils;
+use git2::{Oid, Repository};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 #[cfg(feature = "bindings")]
 use specta::Type;
 
 #[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, Serialize, Deserialize)]
-pub struct StreamCommitsParams {}
+pub struct StreamCommitsParams {
+    /// Maximum number of commits to stream; all remaining commits if omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+
+    /// `nextCursor` of the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<CommitOrder>,
+
+    /// Revisions to start from; `A..B` for the commits reachable from `B` but
+    /// not from `A`, `^A` to exclude `A` and its ancestors. All refs if omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revs: Option<Vec<String>>,
+
+    /// Only commits by one of these author ids.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authors: Option<Vec<String>>,
+
+    /// Only commits touching a path matching one of these prefixes or globs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paths: Option<Vec<String>>,
+
+    /// Only commits committed at or after this time, in seconds since the epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+
+    /// Only commits committed at or before this time, in seconds since the epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+
+    /// Only commits whose message matches this regular expression.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// `true` for merge commits only, `false` to leave them out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merges: Option<bool>,
+
+    /// `exclude` leaves out commits authored by bots, `separate` reports them with
+    /// the author id `bots`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bots: Option<BotFilter>,
+
+    #[serde(rename = "renameThreshold", skip_serializing_if = "Option::is_none")]
+    pub rename_threshold: Option<u16>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copies: Option<bool>,
+
+    #[serde(rename = "copyThreshold", skip_serializing_if = "Option::is_none")]
+    pub copy_threshold: Option<u16>,
+
+    #[serde(rename = "diffMode", skip_serializing_if = "Option::is_none")]
+    pub diff_mode: Option<DiffMode>,
+
+    #[serde(rename = "parseMessage", skip_serializing_if = "Option::is_none")]
+    pub parse_message: Option<bool>,
+
+    #[serde(rename = "lineStats", skip_serializing_if = "Option::is_none")]
+    pub line_stats: Option<bool>,
+}
+
+/// How similar, in percent, a file has to be to a file of the parent commit to
+/// count as renamed or copied from it rather than as added.
+///
+/// Copies are only detected if asked for, and then like `git log -M -C`, only from
+/// files that were modified in the same commit.
+#[derive(Debug, Clone, Copy)]
+pub struct Similarity {
+    rename_threshold: u16,
+    copies: bool,
+    copy_threshold: u16,
+}
+
+impl Default for Similarity {
+    fn default() -> Self {
+        Similarity {
+            rename_threshold: 50,
+            copies: false,
+            copy_threshold: 50,
+        }
+    }
+}
+
+impl Similarity {
+    pub fn new(
+        rename_threshold: Option<u16>,
+        copies: Option<bool>,
+        copy_threshold: Option<u16>,
+    ) -> ExplorerResult<Self> {
+        let default = Similarity::default();
+        let similarity = Similarity {
+            rename_threshold: rename_threshold.unwrap_or(default.rename_threshold),
+            copies: copies.unwrap_or(default.copies),
+            copy_threshold: copy_threshold.unwrap_or(default.copy_threshold),
+        };
+
+        if similarity.rename_threshold > 100 || similarity.copy_threshold > 100 {
+            return Err(ExplorerError::InvalidParams(
+                "similarity thresholds must be between 0 and 100".to_string(),
+            ));
+        }
+
+        Ok(similarity)
+    }
+
+    pub(crate) fn find_options(&self) -> git2::DiffFindOptions {
+        let mut options = git2::DiffFindOptions::new();
+        options
+            .renames(true)
+            .rename_threshold(self.rename_threshold)
+            .copies(self.copies)
+            .copy_threshold(self.copy_threshold);
+        options
+    }
+}
+
+/// The filters of `StreamCommitsParams`, prepared for evaluation during the walk.
+struct CommitFilter {
+    authors: Option<HashSet<String>>,
+    paths: Option<Vec<String>>,
+    since: Option<i64>,
+    until: Option<i64>,
+    message: Option<Regex>,
+    merges: Option<bool>,
+    bots: BotFilter,
+}
+
+impl CommitFilter {
+    fn new(params: &StreamCommitsParams) -> ExplorerResult<Self> {
+        let message = match &params.message {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                ExplorerError::InvalidParams(format!("invalid message pattern: {}", e))
+            })?),
+            None => None,
+        };
+
+        Ok(CommitFilter {
+            authors: params
+                .authors
+                .as_ref()
+                .map(|ids| ids.iter().cloned().collect()),
+            paths: params.paths.clone(),
+            since: params.since,
+            until: params.until,
+            message,
+            merges: params.merges,
+            bots: params.bots.unwrap_or_default(),
+        })
+    }
+
+    /// Cheap checks come first, so most commits never need a diff.
+    fn matches(
+        &self,
+        repo: &Repository,
+        resolver: &AuthorResolver,
+        commit: &git2::Commit,
+    ) -> ExplorerResult<bool> {
+        if let Some(merges) = self.merges {
+            if (commit.parent_count() > 1) != merges {
+                return Ok(false);
+            }
+        }
+
+        let timestamp = commit.time().seconds();
+        if self.since.is_some_and(|since| timestamp < since)
+            || self.until.is_some_and(|until| timestamp > until)
+        {
+            return Ok(false);
+        }
+
+        if self.authors.is_some() || self.bots == BotFilter::Exclude {
+            let (name, email) = utils::signature_identity(&commit.author());
+            let aid = resolver.author_id(&name, &email);
+            let Some(aid) = aid else {
+                return Ok(false);
+            };
+            if self
+                .authors
+                .as_ref()
+                .is_some_and(|authors| !authors.contains(&aid))
+            {
+                return Ok(false);
+            }
+        }
+
+        if let Some(message) = &self.message {
+            if !message.is_match(&String::from_utf8_lossy(commit.message_bytes())) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(paths) = &self.paths {
+            return touches_paths(repo, commit, paths);
+        }
+
+        Ok(true)
+    }
+}
+
+/// The `index`th delta of `diff`, or `None` if it is not a change. Counting lines
+/// means diffing the file, so it is only done with `line_stats`.
+fn file_change(
+    diff: &git2::Diff,
+    index: usize,
+    line_stats: bool,
+) -> ExplorerResult<Option<FileChange>> {
+    let delta = match diff.get_delta(index) {
+        Some(delta) => delta,
+        None => return Ok(None),
+    };
+    let status = match FileStatus::from_delta(delta.status()) {
+        Some(status) => status,
+        None => return Ok(None),
+    };
+
+    let path = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().to_string());
+    let blob = |file: git2::DiffFile| (!file.id().is_zero()).then(|| to_string_oid(&file.id()));
+
+    // generating the patch loads the blobs, which is also what detects binary files;
+    // without it, the flag is only set for blobs git already knew to be binary
+    let (insertions, deletions, binary) = match line_stats {
+        true => match git2::Patch::from_diff(diff, index)? {
+            Some(patch) => {
+                let (_, insertions, deletions) = patch.line_stats()?;
+                (
+                    Some(insertions as u32),
+                    Some(deletions as u32),
+                    patch.delta().flags().is_binary(),
+                )
+            }
+            None => (Some(0), Some(0), delta.flags().is_binary()),
+        },
+        false => (None, None, delta.flags().is_binary()),
+    };
+
+    let old_path = match status {
+        FileStatus::Renamed | FileStatus::Copied => path(delta.old_file()),
+        _ => None,
+    };
+
+    Ok(Some(FileChange {
+        path: path(delta.new_file()).unwrap_or_default(),
+        old_path,
+        status,
+        insertions,
+        deletions,
+        binary,
+        old_oid: blob(delta.old_file()),
+        new_oid: blob(delta.new_file()),
+        parent: None,
+    }))
+}
+
+fn delta_paths(delta: &git2::DiffDelta) -> Vec<String> {
+    [delta.old_file().path(), delta.new_file().path()]
+        .into_iter()
+        .flatten()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
+
+fn changed_paths(diff: &git2::Diff) -> HashSet<String> {
+    diff.deltas()
+        .flat_map(|delta| delta_paths(&delta))
+        .collect()
+}
+
+/// The commits `push_glob("*")` would start from: every ref that points to a commit.
+fn ref_tips(repo: &Repository) -> ExplorerResult<Vec<Oid>> {
+    let mut tips = Vec::new();
+    for reference in repo.references_glob("refs/*")? {
+        if let Ok(commit) = reference?.peel_to_commit() {
+            tips.push(commit.id());
+        }
+    }
+    Ok(tips)
+}
+
+/// The `visited` commits that are no newer than the newest of `frontier` and so may
+/// be among its ancestors. This trusts committer times to grow from parent to child;
+/// where a clock was skewed, a later page can repeat a commit.
+fn revisitable(
+    repo: &Repository,
+    frontier: &[Oid],
+    visited: impl Iterator<Item = Oid>,
+) -> ExplorerResult<Vec<Oid>> {
+    let time = |oid: &Oid| -> ExplorerResult<i64> { Ok(repo.find_commit(*oid)?.time().seconds()) };
+
+    let mut newest = i64::MIN;
+    for oid in frontier {
+        newest = newest.max(time(oid)?);
+    }
+
+    let mut revisitable = Vec::new();
+    for oid in visited {
+        if time(&oid)? <= newest {
+            revisitable.push(oid);
+        }
+    }
+    Ok(revisitable)
+}
+
+/// Where a page of `stream_commits` continues the walk of the previous one, so that
+/// it does not walk the history up to the cursor again.
+struct WalkPosition {
+    /// Commits the walk would have visited next.
+    frontier: Vec<Oid>,
+
+    /// Visited commits the walk may reach again from `frontier`. Ordering by time
+    /// can visit a commit before its child when both have the same timestamp.
+    visited: Vec<Oid>,
+}
+
+impl WalkPosition {
+    fn encode(&self, order: CommitOrder) -> String {
+        let join = |oids: &[Oid]| oids.iter().map(to_string_oid).collect::<Vec<_>>().join(",");
+        let position = format!("{};{}", join(&self.frontier), join(&self.visited));
+        Cursor::encode(order.as_str(), &position)
+    }
+
+    fn decode(repo: &Repository, cursor: &str, order: CommitOrder) -> ExplorerResult<WalkPosition> {
+        let invalid = || ExplorerError::InvalidParams(format!("invalid cursor: {}", cursor));
+        let parse = |oids: &str| -> ExplorerResult<Vec<Oid>> {
+            oids.split(',')
+                .filter(|oid| !oid.is_empty())
+                .map(|oid| {
+                    let oid = Oid::from_str(oid).map_err(|_| invalid())?;
+                    repo.find_commit(oid).map(|_| oid).map_err(|_| invalid())
+                })
+                .collect()
+        };
+
+        let position = Cursor::decode(cursor, order.as_str())?;
+        let (frontier, visited) = position.split_once(';').ok_or_else(invalid)?;
+
+        Ok(WalkPosition {
+            frontier: parse(frontier)?,
+            visited: parse(visited)?,
+        })
+    }
+}
+
+/// Whether the commit changes a file matching `paths` compared to any of its
+/// parents, or adds one if it is a root commit.
+pub(crate) fn touches_paths(
+    repo: &Repository,
+    commit: &git2::Commit,
+    paths: &[String],
+) -> ExplorerResult<bool> {
+    let mut options = git2::DiffOptions::new();
+    for path in paths {
+        options.pathspec(path);
+    }
+
+    let tree = commit.tree()?;
+
+    if commit.parent_count() == 0 {
+        let diff = repo.diff_tree_to_tree(None, Some(&tree), Some(&mut options))?;
+        return Ok(diff.deltas().len() > 0);
+    }
+
+    for parent in commit.parents() {
+        let parent_tree = parent.tree()?;
+        let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(&mut options))?;
+        if diff.deltas().len() > 0 {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitOrder {
+    /// Parents after all of their children, newer commits first otherwise.
+    #[default]
+    Topo,
+    /// Newest committer time first.
+    Time,
+}
+
+impl CommitOrder {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CommitOrder::Topo => "topo",
+            CommitOrder::Time => "time",
+        }
+    }
+
+    fn sorting(&self) -> git2::Sort {
+        match self {
+            CommitOrder::Topo => git2::Sort::TOPOLOGICAL | git2::Sort::TIME,
+            CommitOrder::Time => git2::Sort::TIME,
+        }
+    }
+}
+
+/// Payload of the closing message of `stream_commits`.
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StreamCommitsResult {
+    /// Cursor of the following page, or `null` on the last page.
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
 
 #[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,7 +433,94 @@ pub struct CommitFiles {
     pub deleted: Vec<String>,
     pub modified: Vec<String>,
     pub added: Vec<String>,
+    /// `(old path, new path)`
     pub renamed: Vec<(String, String)>,
+    /// `(source path, new path)`
+    pub copied: Vec<(String, String)>,
+}
+
+/// Which parents the changes of a commit are computed against. All modes are the
+/// same for commits with a single parent.
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffMode {
+    /// Only the first parent, i.e. what a merge brought into the branch.
+    FirstParent,
+    /// Only files that differ from every parent, like `git show --cc`. Their
+    /// changes are reported against the first parent.
+    Combined,
+    /// Every parent separately, tagging each change with its parent.
+    #[default]
+    PerParent,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+    TypeChanged,
+}
+
+impl FileStatus {
+    pub(crate) fn from_delta(delta: git2::Delta) -> Option<Self> {
+        match delta {
+            git2::Delta::Added => Some(FileStatus::Added),
+            git2::Delta::Deleted => Some(FileStatus::Deleted),
+            git2::Delta::Modified => Some(FileStatus::Modified),
+            git2::Delta::Renamed => Some(FileStatus::Renamed),
+            git2::Delta::Copied => Some(FileStatus::Copied),
+            git2::Delta::Typechange => Some(FileStatus::TypeChanged),
+            _ => None,
+        }
+    }
+}
+
+/// A changed file with its line statistics.
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+
+    /// Path the file was renamed or copied from.
+    #[serde(rename = "oldPath")]
+    pub old_path: Option<String>,
+
+    pub status: FileStatus,
+
+    /// Only if `lineStats` was set; `0` for binary files.
+    pub insertions: Option<u32>,
+    pub deletions: Option<u32>,
+
+    /// Without `lineStats` the blobs are not read, so this can miss binary files.
+    pub binary: bool,
+
+    /// Blob before the change; `null` if the file was added.
+    #[serde(rename = "oldOid")]
+    pub old_oid: Option<String>,
+
+    /// Blob after the change; `null` if the file was deleted.
+    #[serde(rename = "newOid")]
+    pub new_oid: Option<String>,
+
+    /// The parent the change is relative to; `null` for root commits.
+    pub parent: Option<String>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CommitStats {
+    /// Only if `lineStats` was set.
+    pub insertions: Option<u32>,
+    pub deletions: Option<u32>,
+
+    #[serde(rename = "filesChanged")]
+    pub files_changed: u32,
 }
 
 #[cfg_attr(feature = "bindings", derive(Type))]
@@ -27,7 +530,17 @@ pub struct Commit {
     pub aid: String,
     pub message: String,
     pub files: CommitFiles,
+    pub changes: Vec<FileChange>,
+    pub stats: CommitStats,
     pub timestamp: String,
+
+    /// How the commit was compared to its parents.
+    #[serde(rename = "diffMode")]
+    pub diff_mode: DiffMode,
+
+    /// Only if `parseMessage` was set.
+    #[serde(rename = "parsedMessage", skip_serializing_if = "Option::is_none")]
+    pub parsed_message: Option<ParsedMessage>,
 }
 
 #[cfg_attr(feature = "bindings", derive(Type))]
@@ -71,36 +584,75 @@ pub struct IsValidRevParams {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetCommitParams {
     pub rev: String,
+
+    /// See `Similarity`; 50 if omitted.
+    #[serde(rename = "renameThreshold", skip_serializing_if = "Option::is_none")]
+    pub rename_threshold: Option<u16>,
+
+    /// Also detect copies, which compares added files against the modified ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copies: Option<bool>,
+
+    /// See `Similarity`; 50 if omitted.
+    #[serde(rename = "copyThreshold", skip_serializing_if = "Option::is_none")]
+    pub copy_threshold: Option<u16>,
+
+    /// `per_parent` if omitted.
+    #[serde(rename = "diffMode", skip_serializing_if = "Option::is_none")]
+    pub diff_mode: Option<DiffMode>,
+
+    /// Also return `parsedMessage`.
+    #[serde(rename = "parseMessage", skip_serializing_if = "Option::is_none")]
+    pub parse_message: Option<bool>,
+
+    /// Also count inserted and deleted lines, per file and in total, which diffs every
+    /// changed file.
+    #[serde(rename = "lineStats", skip_serializing_if = "Option::is_none")]
+    pub line_stats: Option<bool>,
 }
 
-impl Explorer {
-    pub fn get_commit_metadata(&self, rev: &str) -> Result<CommitMeta, git2::Error> {
-        let repo = self.repo.as_ref().unwrap();
+/// What `get_commit` computes besides the commit's metadata.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommitOptions {
+    pub similarity: Similarity,
+    pub diff_mode: DiffMode,
+    pub parse_message: bool,
+    pub line_stats: bool,
 
-        let commit_id = self.get_commit_oid_from_rev(rev)?;
+    /// With `BotFilter::Separate`, commits of bots get the author id `bots`.
+    pub bots: BotFilter,
+}
 
-        let commit = repo.find_commit(commit_id)?;
+impl CommitOptions {
+    fn new(
+        rename_threshold: Option<u16>,
+        copies: Option<bool>,
+        copy_threshold: Option<u16>,
+        diff_mode: Option<DiffMode>,
+        parse_message: Option<bool>,
+        line_stats: Option<bool>,
+    ) -> ExplorerResult<Self> {
+        Ok(CommitOptions {
+            similarity: Similarity::new(rename_threshold, copies, copy_threshold)?,
+            diff_mode: diff_mode.unwrap_or_default(),
+            parse_message: parse_message.unwrap_or(false),
+            line_stats: line_stats.unwrap_or(false),
+            bots: BotFilter::default(),
+        })
+    }
+}
 
-        let author = commit.author();
+impl Explorer {
+    pub fn get_commit_metadata(&self, rev: &str) -> ExplorerResult<CommitMeta> {
+        let repo = self.repo()?;
 
-        let author_name = author.name().unwrap_or_default();
-        let author_email = author.email().unwrap_or_default();
+        let commit_id = self.get_commit_oid_from_rev(rev)?;
 
-        let aid = get_author_id(&author_name.to_string(), &author_email.to_string());
+        let commit = repo.find_commit(commit_id)?;
 
-        /*
-         let mut message = commit.message().unwrap().to_string();
+        let (author_name, author_email) = utils::signature_identity(&commit.author());
 
-        let char_indices = message.char_indices();
-        // ensure message is max 120 chars and only one line
-        if char_indices.count() > 120 {
-            let slice_position = message.char_indices().nth(120).unwrap().0;
-            message = message[0..slice_position].to_string();
-        }
-        if message.contains('\n') {
-            message = message[0..message.find('\n').unwrap()].to_string();
-        }
-        */
+        let aid = self.mailmap()?.author_id(&author_name, &author_email);
 
         let data = CommitMeta {
             oid: to_string_oid(&commit.id()),
@@ -112,72 +664,138 @@ impl Explorer {
         Ok(data)
     }
 
-    pub fn get_commit(&self, rev: &str) -> Result<Commit, git2::Error> {
-        let repo = self.repo.as_ref().unwrap();
+    pub fn get_commit(&self, rev: &str, options: &CommitOptions) -> ExplorerResult<Commit> {
+        let similarity = &options.similarity;
+        let mode = options.diff_mode;
+
+        let repo = self.repo()?;
 
         let meta = self.get_commit_metadata(rev)?;
-        let commit = repo.find_commit(meta.oid.parse().unwrap())?;
+        let commit = repo.find_commit(Oid::from_str(&meta.oid)?)?;
+        let commit_tree = commit.tree()?;
+
+        let diff_to = |parent: Option<&git2::Commit>| -> ExplorerResult<git2::Diff> {
+            let parent_tree = match parent {
+                Some(parent) => Some(parent.tree()?),
+                None => None,
+            };
+            let mut diff =
+                repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
+            diff.find_similar(Some(&mut similarity.find_options()))?;
+            Ok(diff)
+        };
+
+        let parents: Vec<git2::Commit> = commit.parents().collect();
+
+        // root commits are compared to the empty tree
+        let compared: Vec<Option<&git2::Commit>> = match mode {
+            _ if parents.is_empty() => vec![None],
+            DiffMode::PerParent => parents.iter().map(Some).collect(),
+            DiffMode::FirstParent | DiffMode::Combined => vec![parents.first()],
+        };
+
+        // in combined mode, only files that also differ from every other parent are kept
+        let combined_paths = match mode {
+            DiffMode::Combined if parents.len() > 1 => {
+                let mut paths: Option<HashSet<String>> = None;
+                for parent in &parents[1..] {
+                    let changed = changed_paths(&diff_to(Some(parent))?);
+                    paths = Some(match paths {
+                        Some(paths) => paths.intersection(&changed).cloned().collect(),
+                        None => changed,
+                    });
+                }
+                paths
+            }
+            _ => None,
+        };
+
+        let mut changes = Vec::new();
+        let mut stats = CommitStats::default();
+        if options.line_stats {
+            stats.insertions = Some(0);
+            stats.deletions = Some(0);
+        }
+
+        let add = |total: Option<u32>, lines: Option<u32>| Some(total? + lines?);
+
+        for parent in compared {
+            let diff = diff_to(parent)?;
+            let parent_oid = parent.map(|parent| to_string_oid(&parent.id()));
+
+            let kept: Vec<usize> = diff
+                .deltas()
+                .enumerate()
+                .filter(|(_, delta)| match &combined_paths {
+                    Some(paths) => delta_paths(delta).iter().any(|path| paths.contains(path)),
+                    None => true,
+                })
+                .map(|(index, _)| index)
+                .collect();
+
+            for index in kept {
+                if let Some(mut change) = file_change(&diff, index, options.line_stats)? {
+                    stats.insertions = add(stats.insertions, change.insertions);
+                    stats.deletions = add(stats.deletions, change.deletions);
+
+                    change.parent = parent_oid.clone();
+                    changes.push(change);
+                }
+            }
+        }
 
         let mut files: CommitFiles = CommitFiles {
             deleted: Vec::new(),
             modified: Vec::new(),
             added: Vec::new(),
             renamed: Vec::new(),
+            copied: Vec::new(),
         };
-        let commit_tree = commit.tree()?;
-
-        let parents = commit.parent_ids();
-
-        for parent in parents {
-            let parent_commit = repo.find_commit(parent).unwrap();
-            let parent_tree = parent_commit.tree().unwrap();
-            let diff = repo
-                .diff_tree_to_tree(Some(&parent_tree), Some(&commit_tree), None)
-                .unwrap();
-            let deltas = diff.deltas();
-
-            for delta in deltas {
-                let new_file_path = delta
-                    .new_file()
-                    .path()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string();
-                let file_path: String = new_file_path.clone();
-
-                if delta.status() == git2::Delta::Deleted {
-                    files.deleted.push(file_path);
-                } else if delta.status() == git2::Delta::Added {
-                    files.added.push(file_path);
-                } else if delta.status() == git2::Delta::Modified {
-                    files.modified.push(file_path);
-                } else if delta.status() == git2::Delta::Renamed {
-                    let old_file_path = delta
-                        .old_file()
-                        .path()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .to_string();
-                    let old_file_path: String = old_file_path.clone();
-                    files.renamed.push((old_file_path, new_file_path));
-                }
+        for change in &changes {
+            let path = change.path.clone();
+            let old_path = change.old_path.clone().unwrap_or_default();
+            match change.status {
+                FileStatus::Added => files.added.push(path),
+                FileStatus::Deleted => files.deleted.push(path),
+                FileStatus::Modified => files.modified.push(path),
+                FileStatus::Renamed => files.renamed.push((old_path, path)),
+                FileStatus::Copied => files.copied.push((old_path, path)),
+                FileStatus::TypeChanged => {}
             }
+
+            stats.files_changed += 1;
         }
 
+        let parsed_message = options.parse_message.then(|| parse_message(&meta.message));
+
+        let aid = match options.bots {
+            BotFilter::Include => meta.aid,
+            bots => {
+                let (name, email) = utils::signature_identity(&commit.author());
+                self.author_resolver(bots)?
+                    .author_id(&name, &email)
+                    .unwrap_or(meta.aid)
+            }
+        };
+
         Ok(Commit {
             oid: meta.oid,
-            aid: meta.aid,
+            aid,
             message: meta.message,
             timestamp: meta.timestamp,
+            diff_mode: mode,
+            parsed_message,
             files,
+            changes,
+            stats,
         })
     }
 
-    pub fn get_commit_oid_from_rev(&self, rev: &str) -> Result<Oid, Error> {
-        let repo = self.repo.as_ref().unwrap();
-        let oid = repo.revparse_single(rev)?;
+    pub fn get_commit_oid_from_rev(&self, rev: &str) -> ExplorerResult<Oid> {
+        let repo = self.repo()?;
+        let oid = repo
+            .revparse_single(rev)
+            .map_err(|e| ExplorerError::from_rev_lookup(rev, e))?;
 
         let tag = repo.find_tag(oid.id());
 
@@ -195,29 +813,38 @@ impl Explorer {
             }
         }
 
-        let commit = repo.find_commit(oid.id());
+        let commit = repo
+            .find_commit(oid.id())
+            .map_err(|e| ExplorerError::from_rev_lookup(rev, e))?;
 
-        if commit.is_err() {
-            return Err(commit.err().unwrap());
-        }
-
-        let commit = commit.unwrap();
         Ok(commit.id())
     }
 
     pub fn cmd_get_commit(&self, params: &GetCommitParams) {
-        let data = self.get_commit(&params.rev);
+        let data = CommitOptions::new(
+            params.rename_threshold,
+            params.copies,
+            params.copy_threshold,
+            params.diff_mode,
+            params.parse_message,
+            params.line_stats,
+        )
+        .and_then(|options| self.get_commit(&params.rev, &options));
 
-        if data.is_err() {
-            self.send_error(data.err().unwrap().message().to_string());
-            return;
+        match data {
+            Ok(data) => self.send(data, true),
+            Err(e) => self.send_error(e),
         }
-
-        self.send(data.unwrap(), true);
     }
 
     pub fn cmd_is_valid_rev(&self, params: &IsValidRevParams) {
-        let repo = self.repo.as_ref().unwrap();
+        let repo = match self.repo() {
+            Ok(repo) => repo,
+            Err(e) => {
+                self.send_error(e);
+                return;
+            }
+        };
         let rev = params.rev.as_str();
         let oid = repo.revparse_single(rev);
         if oid.is_err() {
@@ -229,40 +856,40 @@ impl Explorer {
     }
 
     pub fn cmd_get_commits_for_time_range(&mut self, params: GetCommitsForTimeRangeParams) {
+        match self.get_commits_for_time_range(&params) {
+            Ok(data) => {
+                self.send(data, true);
+            }
+            Err(e) => {
+                self.send_error(e);
+            }
+        }
+    }
+
+    fn get_commits_for_time_range(
+        &self,
+        params: &GetCommitsForTimeRangeParams,
+    ) -> ExplorerResult<CommitRange> {
         let (start_id, end_id) = self.find_commit_ids_for_time_range(
             &params.branch,
             params.start_seconds,
             params.end_seconds,
-        );
+        )?;
 
-        let until_commit = if let Some(end_id) = end_id {
-            let commit = self.get_commit(&end_id);
-            if commit.is_err() {
-                self.send_error(commit.err().unwrap().message().to_string());
-                return;
-            }
-            Some(commit.unwrap())
-        } else {
-            None
+        let until_commit = match end_id {
+            Some(end_id) => Some(self.get_commit(&end_id, &CommitOptions::default())?),
+            None => None,
         };
 
-        let since_commit = if let Some(start_id) = start_id {
-            let commit = self.get_commit(&start_id);
-            if commit.is_err() {
-                self.send_error(commit.err().unwrap().message().to_string());
-                return;
-            }
-            Some(commit.unwrap())
-        } else {
-            None
+        let since_commit = match start_id {
+            Some(start_id) => Some(self.get_commit(&start_id, &CommitOptions::default())?),
+            None => None,
         };
 
-        let data = CommitRange {
+        Ok(CommitRange {
             since_commit,
             until_commit,
-        };
-
-        self.send(data, true);
+        })
     }
 
     pub fn find_commit_ids_for_time_range(
@@ -270,37 +897,32 @@ impl Explorer {
         rev: &str,
         start_seconds: i64,
         end_seconds: i64,
-    ) -> (Option<String>, Option<String>) {
-        let repo = self.repo.as_ref().unwrap();
+    ) -> ExplorerResult<(Option<String>, Option<String>)> {
+        let repo = self.repo()?;
 
-        let commit_id = self
-            .get_commit_oid_from_rev(rev)
-            .expect("Failed to find commit");
+        let commit_id = self.get_commit_oid_from_rev(rev)?;
 
-        let mut walk = repo.revwalk().expect("Failed to create revwalk");
+        let mut walk = repo.revwalk()?;
 
-        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
-            .expect("Failed to set sorting");
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
 
-        walk.push(commit_id).expect("Failed to push glob");
+        walk.push(commit_id)?;
 
         let mut start_ref: Option<String> = None;
         let mut end_ref: Option<String> = None;
 
-        let branch_commit = repo.find_commit(commit_id).expect("Failed to find commit");
+        let branch_commit = repo.find_commit(commit_id)?;
         let branch_timestamp = branch_commit.time().seconds();
 
         if branch_timestamp >= start_seconds && branch_timestamp <= end_seconds {
             end_ref = Some(to_string_oid(&commit_id));
         }
 
-        for _oid in walk {
-            if _oid.is_err() {
+        for oid in walk {
+            let Ok(oid) = oid else {
                 continue;
-            }
-
-            let oid = _oid.unwrap();
-            let commit = repo.find_commit(oid).expect("Failed to find commit");
+            };
+            let commit = repo.find_commit(oid)?;
             let timestamp = commit.time().seconds();
 
             if end_ref.is_none() && timestamp <= end_seconds && timestamp >= start_seconds {
@@ -314,42 +936,40 @@ impl Explorer {
             }
 
             if start_ref.is_some() && end_ref.is_some() {
-                return (start_ref, end_ref);
+                return Ok((start_ref, end_ref));
             }
         }
 
-        (start_ref, end_ref)
+        Ok((start_ref, end_ref))
     }
 
     pub fn find_commit_ids_for_refs(
         &self,
         start_ref: String,
         end_ref: String,
-    ) -> (Option<String>, Option<String>) {
-        let repo = self.repo.as_ref().unwrap();
+    ) -> ExplorerResult<(Option<String>, Option<String>)> {
+        let repo = self.repo()?;
 
         let start_ref = repo
             .find_reference(&start_ref)
-            .expect("Failed to find start ref");
+            .map_err(|e| ExplorerError::from_rev_lookup(&start_ref, e))?;
 
         let end_ref = repo
             .find_reference(&end_ref)
-            .expect("Failed to find end ref");
+            .map_err(|e| ExplorerError::from_rev_lookup(&end_ref, e))?;
 
-        let start_commit = start_ref
-            .peel_to_commit()
-            .expect("Failed to peel to commit");
+        let start_commit = start_ref.peel_to_commit()?;
 
-        let end_commit = end_ref.peel_to_commit().expect("Failed to peel to commit");
+        let end_commit = end_ref.peel_to_commit()?;
 
         let start_oid = start_commit.id();
 
         let end_oid = end_commit.id();
 
-        (
+        Ok((
             Some(to_string_oid(&start_oid)),
             Some(to_string_oid(&end_oid)),
-        )
+        ))
     }
 
     pub fn pick_last_commit_by_time(
@@ -357,75 +977,609 @@ impl Explorer {
         branch: &str,
         start_seconds: i64,
         end_seconds: i64,
-    ) -> Option<Oid> {
-        let repo = self.repo.as_ref().unwrap();
+    ) -> ExplorerResult<Option<Oid>> {
+        let repo = self.repo()?;
 
         let branch = repo
             .find_branch(branch, git2::BranchType::Local)
-            .expect("Failed to find branch")
+            .map_err(|e| ExplorerError::from_rev_lookup(branch, e))?
             .get()
-            .peel_to_commit()
-            .expect("Failed to peel to commit")
+            .peel_to_commit()?
             .id();
 
-        let mut walk = repo.revwalk().expect("Failed to create revwalk");
+        let mut walk = repo.revwalk()?;
 
-        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
-            .expect("Failed to set sorting");
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
 
-        walk.push(branch).expect("Failed to push glob");
-        for _oid in walk {
-            if _oid.is_err() {
+        walk.push(branch)?;
+        for oid in walk {
+            let Ok(oid) = oid else {
                 continue;
-            }
-
-            let oid = _oid.unwrap();
-            let commit = repo.find_commit(oid).expect("Failed to find commit");
+            };
+            let commit = repo.find_commit(oid)?;
             let timestamp = commit.time().seconds();
             if timestamp >= start_seconds && timestamp <= end_seconds {
-                return Some(oid);
+                return Ok(Some(oid));
             }
         }
-        None
+        Ok(None)
     }
 
-    pub fn cmd_stream_commits(&mut self) {
-        match self.stream_commits() {
+    pub fn cmd_stream_commits(&mut self, params: &StreamCommitsParams) {
+        match self.stream_commits(params) {
             Ok(_) => {}
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
 
-    fn stream_commits(&mut self) -> Result<(), git2::Error> {
-        let stashes = self.get_stash_ids().expect("Failed to get stashes");
-        let repo = self.repo.as_ref().unwrap();
+    fn stream_commits(&mut self, params: &StreamCommitsParams) -> ExplorerResult<()> {
+        validate_limit(params.limit)?;
+        let order = params.order.unwrap_or_default();
+
+        let filter = CommitFilter::new(params)?;
+        let options = CommitOptions {
+            bots: params.bots.unwrap_or_default(),
+            ..CommitOptions::new(
+                params.rename_threshold,
+                params.copies,
+                params.copy_threshold,
+                params.diff_mode,
+                params.parse_message,
+                params.line_stats,
+            )?
+        };
+
+        let stashes = self.get_stash_ids()?;
+        let repo = self.repo()?;
+        let resolver = self.author_resolver(options.bots)?;
 
-        let mut walk = repo.revwalk().expect("Failed to create revwalk");
+        let (tips, hidden) = match &params.revs {
+            Some(revs) => self.resolve_revs(revs)?,
+            None => (ref_tips(repo)?, Vec::new()),
+        };
 
-        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
-            .expect("Failed to set sorting");
+        // a cursor continues the walk where the previous page left it
+        let (tips, skipped) = match &params.cursor {
+            Some(cursor) => {
+                let position = WalkPosition::decode(repo, cursor, order)?;
+                (position.frontier, position.visited)
+            }
+            None => (tips, Vec::new()),
+        };
+        let skipped: HashSet<Oid> = skipped.into_iter().collect();
 
-        walk.push_glob("*").expect("Failed to push glob");
+        let mut walk = repo.revwalk()?;
 
-        for _oid in walk {
-            if _oid.is_err() {
-                continue;
+        walk.set_sorting(order.sorting())?;
+
+        for oid in &tips {
+            walk.push(*oid)?;
+        }
+        for oid in &hidden {
+            walk.hide(*oid)?;
+        }
+
+        // what the walk has reached and visited so far, to tell where it continues
+        let mut reached: HashSet<Oid> = tips.into_iter().collect();
+        let mut visited: HashSet<Oid> = HashSet::new();
+
+        let mut sent: u32 = 0;
+        let mut next_cursor = None;
+
+        for oid in walk {
+            if self.is_cancelled() {
+                self.send_cancelled();
+                return Ok(());
             }
 
-            let oid = _oid.unwrap();
+            let Ok(oid) = oid else {
+                continue;
+            };
+            let commit = repo.find_commit(oid)?;
+
             let oid_str = to_string_oid(&oid);
-            if stashes.contains(&oid_str) {
+            if stashes.contains(&oid_str)
+                || skipped.contains(&oid)
+                || !filter.matches(repo, &resolver, &commit)?
+            {
+                visited.insert(oid);
+                reached.extend(commit.parent_ids());
                 continue;
             }
 
-            let data = self.get_commit(&oid_str)?;
+            if params.limit.is_some_and(|limit| sent >= limit) {
+                // there is at least one more commit, so the page is not the last one
+                let frontier: Vec<Oid> = reached.difference(&visited).copied().collect();
+                let visited = match order {
+                    // parents come after all of their children, so the rest of the walk
+                    // cannot lead back to a visited commit
+                    CommitOrder::Topo => Vec::new(),
+                    CommitOrder::Time => {
+                        revisitable(repo, &frontier, visited.union(&skipped).copied())?
+                    }
+                };
+                next_cursor = Some(WalkPosition { frontier, visited }.encode(order));
+                break;
+            }
+
+            let data = self.get_commit(&oid_str, &options)?;
 
             self.send(data, false);
+            sent += 1;
+            visited.insert(oid);
+            reached.extend(commit.parent_ids());
+        }
+
+        self.send(StreamCommitsResult { next_cursor }, true);
+        Ok(())
+    }
+
+    pub(crate) fn push_revs(
+        &self,
+        walk: &mut git2::Revwalk,
+        revs: &[String],
+    ) -> ExplorerResult<()> {
+        let (pushed, hidden) = self.resolve_revs(revs)?;
+
+        for oid in pushed {
+            walk.push(oid)?;
+        }
+        for oid in hidden {
+            walk.hide(oid)?;
         }
 
-        self.send((), true);
         Ok(())
     }
+
+    /// Splits `revs` into the commits to walk from and the commits to exclude,
+    /// along with their ancestors.
+    fn resolve_revs(&self, revs: &[String]) -> ExplorerResult<(Vec<Oid>, Vec<Oid>)> {
+        if revs.is_empty() {
+            return Err(ExplorerError::InvalidParams(
+                "revs must not be empty".to_string(),
+            ));
+        }
+
+        let mut pushed = Vec::new();
+        let mut hidden = Vec::new();
+
+        for rev in revs {
+            if let Some((from, to)) = rev.split_once("..") {
+                if to.starts_with('.') {
+                    return Err(ExplorerError::InvalidParams(format!(
+                        "symmetric ranges are not supported: {}",
+                        rev
+                    )));
+                }
+
+                // like git, an omitted end of a range means HEAD
+                let from = if from.is_empty() { "HEAD" } else { from };
+                let to = if to.is_empty() { "HEAD" } else { to };
+
+                hidden.push(self.get_commit_oid_from_rev(from)?);
+                pushed.push(self.get_commit_oid_from_rev(to)?);
+            } else if let Some(excluded) = rev.strip_prefix('^') {
+                hidden.push(self.get_commit_oid_from_rev(excluded)?);
+            } else {
+                pushed.push(self.get_commit_oid_from_rev(rev)?);
+            }
+        }
+
+        Ok((pushed, hidden))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    /// Runs `stream_commits`, returning the ids of the streamed commits and the
+    /// cursor of the next page.
+    fn stream(explorer: &mut Explorer, params: Value) -> (Vec<String>, Option<String>) {
+        let request = json!({ "id": 1, "method": "stream_commits", "params": params });
+        let request = Explorer::deserialize_request(request.to_string()).unwrap();
+
+        let responses = Arc::new(Mutex::new(Vec::new()));
+        let seen = responses.clone();
+        explorer.handle(request, move |response| {
+            seen.lock()
+                .unwrap()
+                .push(serde_json::to_value(&response).unwrap());
+        });
+
+        let responses = responses.lock().unwrap();
+        let (last, commits) = responses.split_last().unwrap();
+        assert!(last.get("error").is_none(), "{}", last);
+
+        let oids = commits
+            .iter()
+            .map(|commit| commit["data"]["oid"].as_str().unwrap().to_string())
+            .collect();
+        (
+            oids,
+            last["data"]["nextCursor"].as_str().map(str::to_string),
+        )
+    }
+
+    /// Two branches merged into `main`, with commits on both sides that share their
+    /// timestamps, and a third branch left unmerged.
+    fn branchy_repo(name: &str) -> TestRepo {
+        const T: i64 = 1_800_000_000;
+
+        let repo = TestRepo::new(name);
+        let base = repo.commit("base").file("a.txt", "0").create();
+        let main = repo.commit("main 1").file("a.txt", "1").time(T).create();
+        let side = repo
+            .commit("side 1")
+            .on("refs/heads/side")
+            .parents(&[base])
+            .file("b.txt", "1")
+            .time(T)
+            .create();
+        repo.commit("side 2")
+            .on("refs/heads/side")
+            .file("b.txt", "2")
+            .time(T + 1_000)
+            .create();
+        repo.commit("other")
+            .on("refs/heads/other")
+            .parents(&[side])
+            .file("c.txt", "1")
+            .time(T + 2_000)
+            .create();
+        let side = repo.repo.refname_to_id("refs/heads/side").unwrap();
+        repo.commit("merge")
+            .parents(&[main, side])
+            .time(T + 3_000)
+            .create();
+        repo.commit("main 2")
+            .file("a.txt", "2")
+            .time(T + 3_000)
+            .create();
+        repo
+    }
+
+    #[test]
+    fn walk_positions_round_trip() {
+        let repo = TestRepo::new("walk-position");
+        let first = repo.commit("first").create();
+        let second = repo.commit("second").create();
+
+        let position = WalkPosition {
+            frontier: vec![second],
+            visited: vec![first],
+        };
+        let cursor = position.encode(CommitOrder::Time);
+
+        let decoded = WalkPosition::decode(&repo.repo, &cursor, CommitOrder::Time).unwrap();
+        assert_eq!(decoded.frontier, [second]);
+        assert_eq!(decoded.visited, [first]);
+
+        assert!(WalkPosition::decode(&repo.repo, &cursor, CommitOrder::Topo).is_err());
+
+        // a commit the repository does not have
+        let unknown = Cursor::encode("time", &format!("{};", "1".repeat(40)));
+        assert!(matches!(
+            WalkPosition::decode(&repo.repo, &unknown, CommitOrder::Time),
+            Err(ExplorerError::InvalidParams(_))
+        ));
+    }
+
+    #[test]
+    fn pages_resume_the_walk() {
+        let repo = branchy_repo("commit-pages");
+        let mut explorer = repo.explorer();
+
+        for order in ["topo", "time"] {
+            let (all, cursor) = stream(&mut explorer, json!({ "order": order }));
+            assert_eq!(all.len(), 7);
+            assert_eq!(cursor, None);
+
+            let mut paged = Vec::new();
+            let mut cursor = None;
+            loop {
+                let params = json!({ "order": order, "limit": 2, "cursor": cursor });
+                let (page, next) = stream(&mut explorer, params);
+                assert!(page.len() <= 2);
+                paged.extend(page);
+                match next {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+
+            let unique: HashSet<_> = paged.iter().collect();
+            assert_eq!(unique.len(), paged.len(), "{} pages repeat commits", order);
+            assert_eq!(unique, all.iter().collect(), "{} pages miss commits", order);
+        }
+    }
+
+    #[test]
+    fn filters_the_walk() {
+        let repo = TestRepo::new("commit-filters");
+        let root = repo.commit("add a").file("src/a.rs", "a").create();
+        let docs = repo
+            .commit("docs: add readme")
+            .author("Bob", "bob@x.org")
+            .file("docs/readme.md", "hi")
+            .create();
+        let side = repo
+            .commit("add b")
+            .author("Bob", "bob@x.org")
+            .on("refs/heads/side")
+            .parents(&[root])
+            .file("src/b.rs", "b")
+            .create();
+        let merge = repo.commit("merge side").parents(&[docs, side]).create();
+        let fix = repo.commit("fix: a").file("src/a.rs", "a2").create();
+
+        let mut explorer = repo.explorer();
+        let mut matching = |params: Value| -> HashSet<String> {
+            stream(&mut explorer, params).0.into_iter().collect()
+        };
+        let commits =
+            |oids: &[Oid]| -> HashSet<String> { oids.iter().map(to_string_oid).collect() };
+
+        // a merge touches whatever differs from either parent
+        assert_eq!(
+            matching(json!({ "paths": ["src"] })),
+            commits(&[root, side, merge, fix])
+        );
+        assert_eq!(
+            matching(json!({ "paths": ["*.md"] })),
+            commits(&[docs, merge])
+        );
+
+        let bob = crate::utils::get_author_id("Bob", "bob@x.org");
+        assert_eq!(
+            matching(json!({ "authors": [bob] })),
+            commits(&[docs, side])
+        );
+
+        assert_eq!(matching(json!({ "merges": true })), commits(&[merge]));
+        assert_eq!(
+            matching(json!({ "merges": false })),
+            commits(&[root, docs, side, fix])
+        );
+
+        assert_eq!(matching(json!({ "message": "^fix:" })), commits(&[fix]));
+
+        let time = |oid: Oid| repo.repo.find_commit(oid).unwrap().time().seconds();
+        let window = json!({ "since": time(side), "until": time(merge) });
+        assert_eq!(matching(window), commits(&[side, merge]));
+
+        assert_eq!(
+            matching(json!({ "revs": ["side"] })),
+            commits(&[root, side])
+        );
+        assert_eq!(
+            matching(json!({ "revs": ["side..main"] })),
+            commits(&[docs, merge, fix])
+        );
+
+        let alice = crate::utils::get_author_id("Alice", "alice@x.org");
+        let combined = json!({ "authors": [alice], "paths": ["src"], "merges": false });
+        assert_eq!(matching(combined), commits(&[root, fix]));
+    }
+
+    /// `count` distinct lines, numbered from `first`.
+    fn lines(name: &str, first: usize, count: usize) -> String {
+        (first..first + count)
+            .map(|i| format!("{} is line number {} of the file\n", name, i))
+            .collect()
+    }
+
+    fn changed_files(explorer: &Explorer, oid: Oid, options: &CommitOptions) -> CommitFiles {
+        explorer
+            .get_commit(&to_string_oid(&oid), options)
+            .unwrap()
+            .files
+    }
+
+    #[test]
+    fn detects_renames_above_the_threshold() {
+        let repo = TestRepo::new("renames");
+        repo.commit("add")
+            .file("src/long.txt", lines("long", 0, 10))
+            .create();
+        // 8 of 10 lines kept
+        let moved = repo
+            .commit("move")
+            .remove("src/long.txt")
+            .file("lib/long.txt", lines("long", 0, 8) + &lines("new", 0, 2))
+            .create();
+        let explorer = repo.explorer();
+
+        let files = changed_files(&explorer, moved, &CommitOptions::default());
+        let renamed = ("src/long.txt".to_string(), "lib/long.txt".to_string());
+        assert_eq!(files.renamed, [renamed]);
+        assert!(files.added.is_empty() && files.deleted.is_empty());
+
+        let strict = CommitOptions::new(Some(90), None, None, None, None, None).unwrap();
+        let files = changed_files(&explorer, moved, &strict);
+        assert!(files.renamed.is_empty());
+        assert_eq!(files.added, ["lib/long.txt"]);
+        assert_eq!(files.deleted, ["src/long.txt"]);
+    }
+
+    #[test]
+    fn detects_copies_only_when_asked_to() {
+        let repo = TestRepo::new("copies");
+        repo.commit("add").file("a.txt", lines("a", 0, 10)).create();
+        // copies are only looked for among modified files, as with `git log -C`
+        let copied = repo
+            .commit("copy")
+            .file("a.txt", lines("a", 0, 11))
+            .file("b.txt", lines("a", 0, 8) + &lines("b", 0, 2))
+            .create();
+        let explorer = repo.explorer();
+
+        let files = changed_files(&explorer, copied, &CommitOptions::default());
+        assert!(files.copied.is_empty());
+        assert_eq!(files.added, ["b.txt"]);
+
+        let copies = CommitOptions::new(None, Some(true), None, None, None, None).unwrap();
+        let files = changed_files(&explorer, copied, &copies);
+        assert_eq!(files.copied, [("a.txt".to_string(), "b.txt".to_string())]);
+        assert_eq!(files.modified, ["a.txt"]);
+        assert!(files.added.is_empty());
+
+        let strict = CommitOptions::new(None, Some(true), Some(90), None, None, None).unwrap();
+        let files = changed_files(&explorer, copied, &strict);
+        assert!(files.copied.is_empty());
+        assert_eq!(files.added, ["b.txt"]);
+    }
+
+    #[test]
+    fn rejects_thresholds_above_100() {
+        assert!(matches!(
+            Similarity::new(Some(101), None, None),
+            Err(ExplorerError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            Similarity::new(None, Some(true), Some(101)),
+            Err(ExplorerError::InvalidParams(_))
+        ));
+        assert!(Similarity::new(Some(100), Some(true), Some(0)).is_ok());
+    }
+
+    #[test]
+    fn counts_lines_only_with_line_stats() {
+        let repo = TestRepo::new("line-stats");
+        repo.commit("add").file("a.txt", lines("a", 0, 10)).create();
+        let changed = repo
+            .commit("change")
+            .file("a.txt", lines("a", 0, 8))
+            .file("b.txt", lines("b", 0, 3))
+            .create();
+        let explorer = repo.explorer();
+        let oid = to_string_oid(&changed);
+
+        let commit = explorer
+            .get_commit(&oid, &CommitOptions::default())
+            .unwrap();
+        assert_eq!(commit.stats.insertions, None);
+        assert_eq!(commit.stats.deletions, None);
+        assert_eq!(commit.stats.files_changed, 2);
+        assert!(commit
+            .changes
+            .iter()
+            .all(|change| change.insertions.is_none()));
+
+        let line_stats = CommitOptions::new(None, None, None, None, None, Some(true)).unwrap();
+        let commit = explorer.get_commit(&oid, &line_stats).unwrap();
+        assert_eq!(commit.stats.insertions, Some(3));
+        assert_eq!(commit.stats.deletions, Some(2));
+        assert_eq!(commit.stats.files_changed, 2);
+    }
+
+    /// Paths of `changes`, each with the parent it is relative to.
+    fn changes_by_parent(commit: &Commit) -> Vec<(String, String)> {
+        commit
+            .changes
+            .iter()
+            .map(|change| (change.parent.clone().unwrap(), change.path.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn compares_merges_by_diff_mode() {
+        let repo = TestRepo::new("diff-modes");
+        let base = repo
+            .commit("base")
+            .file("a.txt", "a\n")
+            .file("b.txt", "b\n")
+            .file("d.txt", "d\n")
+            .create();
+        let main = repo
+            .commit("main")
+            .file("a.txt", "a1\n")
+            .file("d.txt", "d1\n")
+            .create();
+        let side = repo
+            .commit("side")
+            .on("refs/heads/side")
+            .parents(&[base])
+            .file("b.txt", "b1\n")
+            .file("d.txt", "d2\n")
+            .create();
+        // `d.txt` resolves a conflict and `c.txt` is new to both sides
+        let merge = repo
+            .commit("merge")
+            .parents(&[main, side])
+            .file("b.txt", "b1\n")
+            .file("c.txt", "c\n")
+            .file("d.txt", "d3\n")
+            .create();
+        let explorer = repo.explorer();
+        let (main, side) = (to_string_oid(&main), to_string_oid(&side));
+
+        let get = |diff_mode| {
+            let options = CommitOptions::new(None, None, None, Some(diff_mode), None, None);
+            let commit = explorer.get_commit(&to_string_oid(&merge), &options.unwrap());
+            changes_by_parent(&commit.unwrap())
+        };
+        let on = |parent: &String, path: &str| (parent.clone(), path.to_string());
+
+        assert_eq!(
+            get(DiffMode::FirstParent),
+            [on(&main, "b.txt"), on(&main, "c.txt"), on(&main, "d.txt")]
+        );
+        assert_eq!(
+            get(DiffMode::PerParent),
+            [
+                on(&main, "b.txt"),
+                on(&main, "c.txt"),
+                on(&main, "d.txt"),
+                on(&side, "a.txt"),
+                on(&side, "c.txt"),
+                on(&side, "d.txt"),
+            ]
+        );
+        assert_eq!(
+            get(DiffMode::Combined),
+            [on(&main, "c.txt"), on(&main, "d.txt")]
+        );
+
+        // a single parent is compared the same way in every mode
+        for diff_mode in [
+            DiffMode::FirstParent,
+            DiffMode::Combined,
+            DiffMode::PerParent,
+        ] {
+            let options = CommitOptions::new(None, None, None, Some(diff_mode), None, None);
+            let commit = explorer.get_commit(&main, &options.unwrap()).unwrap();
+            assert_eq!(commit.diff_mode, diff_mode);
+            assert_eq!(commit.files.modified, ["a.txt", "d.txt"]);
+        }
+    }
+
+    #[test]
+    fn identifies_authors_whose_names_are_not_utf8() {
+        let repo = TestRepo::new("commits-latin1");
+        let oid = repo
+            .commit("one")
+            .author_bytes(b"Ren\xe9", "rene@x.org")
+            .file("a.txt", "1")
+            .create();
+        let mut explorer = repo.explorer();
+
+        let aid = "v1-be44ffc180de2aa9";
+        let meta = explorer.get_commit_metadata(&to_string_oid(&oid)).unwrap();
+        assert_eq!(meta.aid, aid);
+        assert_eq!(
+            meta.aid,
+            crate::utils::get_author_id("Ren\u{fffd}", "rene@x.org")
+        );
+
+        let (oids, _) = stream(&mut explorer, json!({ "authors": [aid] }));
+        assert_eq!(oids, [to_string_oid(&oid)]);
+    }
 }
@@ -0,0 +1,431 @@
+use git2::{Diff, DiffOptions, Patch};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bindings")]
+use specta::Type;
+
+use crate::commits::{FileStatus, Similarity};
+use crate::error::ExplorerResult;
+use crate::explorer::Explorer;
+use crate::git_graph::to_string_oid;
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitespaceMode {
+    #[default]
+    Strict,
+    /// Like `git diff -w`.
+    IgnoreAll,
+    /// Like `git diff -b`.
+    IgnoreChange,
+    /// Like `git diff --ignore-space-at-eol`.
+    IgnoreEol,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetDiffParams {
+    /// The new side of the diff.
+    pub rev: String,
+
+    /// The old side of the diff; the first parent of `rev` if omitted, or the
+    /// empty tree if `rev` is a root commit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+
+    /// Only files matching one of these prefixes or globs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paths: Option<Vec<String>>,
+
+    /// Unchanged lines around each change; 3 if omitted.
+    #[serde(rename = "contextLines", skip_serializing_if = "Option::is_none")]
+    pub context_lines: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub whitespace: Option<WhitespaceMode>,
+
+    /// Also return the diff as unified diff text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<bool>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffLine {
+    /// `+`, `-` or ` `; `=`, `>` or `<` for "no newline at end of file" on
+    /// both sides, only the old side or only the new side.
+    pub origin: String,
+
+    pub content: String,
+
+    #[serde(rename = "oldLineno")]
+    pub old_lineno: Option<u32>,
+
+    #[serde(rename = "newLineno")]
+    pub new_lineno: Option<u32>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// The `@@ -a,b +c,d @@` line, including the function context git finds.
+    pub header: String,
+
+    #[serde(rename = "oldStart")]
+    pub old_start: u32,
+
+    #[serde(rename = "oldLines")]
+    pub old_lines: u32,
+
+    #[serde(rename = "newStart")]
+    pub new_start: u32,
+
+    #[serde(rename = "newLines")]
+    pub new_lines: u32,
+
+    pub lines: Vec<DiffLine>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+
+    /// Path the file was renamed or copied from.
+    #[serde(rename = "oldPath")]
+    pub old_path: Option<String>,
+
+    pub status: FileStatus,
+
+    /// Binary files have no hunks.
+    pub binary: bool,
+
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffResult {
+    /// Commit of the old side; `null` for the empty tree.
+    pub from: Option<String>,
+
+    /// Commit of the new side.
+    pub to: String,
+
+    pub files: Vec<FileDiff>,
+
+    /// Unified diff text, if `raw` was set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+impl Explorer {
+    pub fn cmd_get_diff(&self, params: &GetDiffParams) {
+        match self.get_diff(params) {
+            Ok(result) => self.send(result, true),
+            Err(e) => self.send_error(e),
+        }
+    }
+
+    fn get_diff(&self, params: &GetDiffParams) -> ExplorerResult<DiffResult> {
+        let repo = self.repo()?;
+
+        let to = repo.find_commit(self.get_commit_oid_from_rev(&params.rev)?)?;
+        let from = match &params.from {
+            Some(from) => Some(repo.find_commit(self.get_commit_oid_from_rev(from)?)?),
+            None if to.parent_count() > 0 => Some(to.parent(0)?),
+            None => None,
+        };
+
+        let mut options = DiffOptions::new();
+        options.context_lines(params.context_lines.unwrap_or(3));
+        match params.whitespace.unwrap_or_default() {
+            WhitespaceMode::Strict => {}
+            WhitespaceMode::IgnoreAll => {
+                options.ignore_whitespace(true);
+            }
+            WhitespaceMode::IgnoreChange => {
+                options.ignore_whitespace_change(true);
+            }
+            WhitespaceMode::IgnoreEol => {
+                options.ignore_whitespace_eol(true);
+            }
+        }
+        for path in params.paths.iter().flatten() {
+            options.pathspec(path);
+        }
+
+        let from_tree = match &from {
+            Some(from) => Some(from.tree()?),
+            None => None,
+        };
+        let mut diff =
+            repo.diff_tree_to_tree(from_tree.as_ref(), Some(&to.tree()?), Some(&mut options))?;
+        diff.find_similar(Some(&mut Similarity::default().find_options()))?;
+
+        let mut files = Vec::new();
+        for index in 0..diff.deltas().len() {
+            if let Some(file) = file_diff(&diff, index)? {
+                files.push(file);
+            }
+        }
+
+        let patch = match params.raw {
+            Some(true) => Some(unified_text(&diff)?),
+            _ => None,
+        };
+
+        Ok(DiffResult {
+            from: from.map(|from| to_string_oid(&from.id())),
+            to: to_string_oid(&to.id()),
+            files,
+            patch,
+        })
+    }
+}
+
+fn file_diff(diff: &Diff, index: usize) -> ExplorerResult<Option<FileDiff>> {
+    let patch = match Patch::from_diff(diff, index)? {
+        Some(patch) => patch,
+        None => return Ok(None),
+    };
+
+    let delta = patch.delta();
+    let status = match FileStatus::from_delta(delta.status()) {
+        Some(status) => status,
+        None => return Ok(None),
+    };
+
+    let path = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().to_string());
+    let old_path = match status {
+        FileStatus::Renamed | FileStatus::Copied => path(delta.old_file()),
+        _ => None,
+    };
+
+    let mut hunks = Vec::new();
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, num_lines) = patch.hunk(hunk_index)?;
+
+        let mut lines = Vec::with_capacity(num_lines);
+        for line_index in 0..num_lines {
+            let line = patch.line_in_hunk(hunk_index, line_index)?;
+            lines.push(DiffLine {
+                origin: line.origin().to_string(),
+                content: String::from_utf8_lossy(line.content()).to_string(),
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+            });
+        }
+
+        hunks.push(DiffHunk {
+            header: String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string(),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+
+    Ok(Some(FileDiff {
+        path: path(delta.new_file()).unwrap_or_default(),
+        old_path,
+        status,
+        binary: delta.flags().is_binary(),
+        hunks,
+    }))
+}
+
+fn unified_text(diff: &Diff) -> ExplorerResult<String> {
+    let mut text = String::new();
+    diff.print(git2::DiffFormat::Patch, |_, _, line| {
+        if let '+' | '-' | ' ' = line.origin() {
+            text.push(line.origin());
+        }
+        text.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Oid;
+
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    /// Lines `l1` to `l20`, with the numbered ones in `changed` edited.
+    fn numbered(changed: &[u32]) -> String {
+        (1..=20)
+            .map(|i| match changed.contains(&i) {
+                true => format!("l{} changed\n", i),
+                false => format!("l{}\n", i),
+            })
+            .collect()
+    }
+
+    fn params(rev: Oid) -> GetDiffParams {
+        GetDiffParams {
+            rev: to_string_oid(&rev),
+            from: None,
+            paths: None,
+            context_lines: None,
+            whitespace: None,
+            raw: None,
+        }
+    }
+
+    fn origins(hunk: &DiffHunk) -> String {
+        hunk.lines.iter().map(|line| line.origin.as_str()).collect()
+    }
+
+    #[test]
+    fn splits_changes_into_hunks() {
+        let repo = TestRepo::new("diff-hunks");
+        repo.commit("add").file("a.txt", numbered(&[])).create();
+        let changed = repo
+            .commit("change")
+            .file("a.txt", numbered(&[5, 15]))
+            .create();
+        let explorer = repo.explorer();
+
+        let diff = explorer.get_diff(&params(changed)).unwrap();
+        assert_eq!(diff.files.len(), 1);
+        let hunks = &diff.files[0].hunks;
+        assert_eq!(hunks.len(), 2);
+
+        let hunk = &hunks[0];
+        // every line looks like a function name to git, so the last one before
+        // the hunk is its context
+        assert_eq!(hunk.header, "@@ -2,7 +2,7 @@ l1");
+        assert_eq!(
+            (
+                hunk.old_start,
+                hunk.old_lines,
+                hunk.new_start,
+                hunk.new_lines
+            ),
+            (2, 7, 2, 7)
+        );
+        assert_eq!(origins(hunk), "   -+   ");
+        let removed = &hunk.lines[3];
+        assert_eq!(removed.content, "l5\n");
+        assert_eq!((removed.old_lineno, removed.new_lineno), (Some(5), None));
+        let added = &hunk.lines[4];
+        assert_eq!(added.content, "l5 changed\n");
+        assert_eq!((added.old_lineno, added.new_lineno), (None, Some(5)));
+        assert_eq!(hunks[1].header, "@@ -12,7 +12,7 @@ l11");
+
+        // with more context, both changes share a hunk
+        let diff = explorer
+            .get_diff(&GetDiffParams {
+                context_lines: Some(5),
+                ..params(changed)
+            })
+            .unwrap();
+        assert_eq!(diff.files[0].hunks.len(), 1);
+
+        let diff = explorer
+            .get_diff(&GetDiffParams {
+                context_lines: Some(0),
+                ..params(changed)
+            })
+            .unwrap();
+        let hunk = &diff.files[0].hunks[0];
+        assert_eq!(hunk.header, "@@ -5 +5 @@ l4");
+        assert_eq!(origins(hunk), "-+");
+    }
+
+    #[test]
+    fn marks_missing_newlines_by_side() {
+        let repo = TestRepo::new("diff-newlines");
+        repo.commit("add").file("a.txt", "a\nb\n").create();
+        let dropped = repo.commit("drop newline").file("a.txt", "a\nc").create();
+        let restored = repo
+            .commit("restore newline")
+            .file("a.txt", "a\nc\n")
+            .create();
+        let explorer = repo.explorer();
+
+        // only the new side is missing the newline
+        let diff = explorer.get_diff(&params(dropped)).unwrap();
+        assert_eq!(origins(&diff.files[0].hunks[0]), " -+<");
+
+        let diff = explorer.get_diff(&params(restored)).unwrap();
+        assert_eq!(origins(&diff.files[0].hunks[0]), " ->+");
+    }
+
+    #[test]
+    fn ignores_whitespace_when_asked_to() {
+        let repo = TestRepo::new("diff-whitespace");
+        repo.commit("add").file("a.txt", "a b\nc\n").create();
+        let eol = repo.commit("eol").file("a.txt", "a b  \nc\n").create();
+        let inner = repo.commit("inner").file("a.txt", "a   b  \nc\n").create();
+        let explorer = repo.explorer();
+
+        let hunks = |rev, whitespace| {
+            let params = GetDiffParams {
+                whitespace: Some(whitespace),
+                ..params(rev)
+            };
+            let diff = explorer.get_diff(&params).unwrap();
+            diff.files
+                .iter()
+                .map(|file| file.hunks.len())
+                .sum::<usize>()
+        };
+
+        assert_eq!(hunks(eol, WhitespaceMode::Strict), 1);
+        assert_eq!(hunks(eol, WhitespaceMode::IgnoreEol), 0);
+        assert_eq!(hunks(inner, WhitespaceMode::IgnoreEol), 1);
+        assert_eq!(hunks(inner, WhitespaceMode::IgnoreChange), 0);
+        assert_eq!(hunks(inner, WhitespaceMode::IgnoreAll), 0);
+    }
+
+    #[test]
+    fn compares_against_the_requested_side() {
+        let repo = TestRepo::new("diff-sides");
+        let root = repo
+            .commit("add")
+            .file("a.txt", "a\n")
+            .file("docs/b.md", "b\n")
+            .create();
+        repo.commit("change a").file("a.txt", "a1\n").create();
+        let tip = repo.commit("change b").file("docs/b.md", "b1\n").create();
+        let explorer = repo.explorer();
+
+        let paths = |diff: &DiffResult| -> Vec<String> {
+            diff.files.iter().map(|file| file.path.clone()).collect()
+        };
+
+        let diff = explorer.get_diff(&params(root)).unwrap();
+        assert_eq!(diff.from, None);
+        assert_eq!(paths(&diff), ["a.txt", "docs/b.md"]);
+
+        let diff = explorer.get_diff(&params(tip)).unwrap();
+        assert_eq!(paths(&diff), ["docs/b.md"]);
+
+        let from_root = GetDiffParams {
+            from: Some(to_string_oid(&root)),
+            raw: Some(true),
+            ..params(tip)
+        };
+        let diff = explorer.get_diff(&from_root).unwrap();
+        assert_eq!(diff.from, Some(to_string_oid(&root)));
+        assert_eq!(paths(&diff), ["a.txt", "docs/b.md"]);
+        let patch = diff.patch.unwrap();
+        assert!(patch.contains("diff --git a/a.txt b/a.txt\n"));
+        assert!(patch.contains("\n-b\n+b1\n"));
+
+        let only_docs = GetDiffParams {
+            paths: Some(vec!["docs".to_string()]),
+            ..from_root
+        };
+        let diff = explorer.get_diff(&only_docs).unwrap();
+        assert_eq!(paths(&diff), ["docs/b.md"]);
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bindings")]
+use specta::Type;
+
+use crate::repositories::RepoHandle;
+
+/// Machine-readable identifier of an `ExplorerError`, sent as `code` in every `ErrorResponse`.
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NoRepositoryOpen,
+    UnknownRepository,
+    RevNotFound,
+    PathNotFound,
+    InvalidParams,
+    GitError,
+    Internal,
+    /// Never sent by the explorer, which ends a cancelled request with `cancelled`
+    /// instead; for hosts that report the cancellation as an error.
+    Cancelled,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitErrorInfo {
+    pub class: String,
+    pub code: String,
+}
+
+#[derive(Debug)]
+pub enum ExplorerError {
+    NoRepositoryOpen,
+    UnknownRepository(RepoHandle),
+    RevNotFound(String),
+    PathNotFound(String),
+    InvalidParams(String),
+    GitError {
+        class: git2::ErrorClass,
+        code: git2::ErrorCode,
+        message: String,
+    },
+    Internal(String),
+}
+
+pub type ExplorerResult<T> = Result<T, ExplorerError>;
+
+impl ExplorerError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ExplorerError::NoRepositoryOpen => ErrorCode::NoRepositoryOpen,
+            ExplorerError::UnknownRepository(_) => ErrorCode::UnknownRepository,
+            ExplorerError::RevNotFound(_) => ErrorCode::RevNotFound,
+            ExplorerError::PathNotFound(_) => ErrorCode::PathNotFound,
+            ExplorerError::InvalidParams(_) => ErrorCode::InvalidParams,
+            ExplorerError::GitError { .. } => ErrorCode::GitError,
+            ExplorerError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    pub fn git_info(&self) -> Option<GitErrorInfo> {
+        match self {
+            ExplorerError::GitError { class, code, .. } => Some(GitErrorInfo {
+                class: format!("{:?}", class),
+                code: format!("{:?}", code),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Maps a failed rev lookup to `RevNotFound`, keeping other git failures as they are.
+    pub fn from_rev_lookup(rev: &str, err: git2::Error) -> ExplorerError {
+        match err.code() {
+            git2::ErrorCode::NotFound | git2::ErrorCode::InvalidSpec => {
+                ExplorerError::RevNotFound(rev.to_string())
+            }
+            _ => err.into(),
+        }
+    }
+
+    /// Maps a failed path lookup to `PathNotFound`, keeping other git failures as they are.
+    pub fn from_path_lookup(path: &str, err: git2::Error) -> ExplorerError {
+        match err.code() {
+            git2::ErrorCode::NotFound => ExplorerError::PathNotFound(path.to_string()),
+            _ => err.into(),
+        }
+    }
+}
+
+impl fmt::Display for ExplorerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExplorerError::NoRepositoryOpen => write!(f, "No repository is open"),
+            ExplorerError::UnknownRepository(handle) => {
+                write!(f, "No repository is open with handle {}", handle)
+            }
+            ExplorerError::RevNotFound(rev) => write!(f, "Revision not found: {}", rev),
+            ExplorerError::PathNotFound(path) => write!(f, "Path not found: {}", path),
+            ExplorerError::InvalidParams(message) => write!(f, "Invalid parameters: {}", message),
+            ExplorerError::GitError { message, .. } => write!(f, "{}", message),
+            ExplorerError::Internal(message) => write!(f, "Internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ExplorerError {}
+
+impl From<git2::Error> for ExplorerError {
+    fn from(err: git2::Error) -> Self {
+        ExplorerError::GitError {
+            class: err.class(),
+            code: err.code(),
+            message: err.message().to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ExplorerError {
+    fn from(err: serde_json::Error) -> Self {
+        ExplorerError::InvalidParams(err.to_string())
+    }
+}
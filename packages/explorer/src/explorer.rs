@@ -1,3 +1,4 @@
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use git2::{BranchType, Repository};
@@ -6,25 +7,42 @@ use serde::Serialize;
 #[cfg(feature = "bindings")]
 use specta::Type;
 
-use crate::authors::StreamAuthorsParams;
+use crate::authors::{GetAuthorsParams, MapLegacyAuthorIdsParams, StreamAuthorsParams};
 use crate::blame::BlameParams;
+use crate::bots::BotDetector;
+use crate::cancellation::{CancelParams, CancellationPoll, CancellationRegistry};
+use crate::error::{ErrorCode, ExplorerError, ExplorerResult, GitErrorInfo};
 use crate::branches::GetCommitsForBranchParams;
 use crate::commits::{
-    self, GetCommitParams, GetCommitsForTimeRangeParams, IsValidRevParams, StreamCommitsParams,
+    self, CommitOptions, GetCommitParams, GetCommitsForTimeRangeParams, IsValidRevParams,
+    StreamCommitsParams,
 };
+use crate::diff::GetDiffParams;
 use crate::file_content::GetFileContentParams;
-use crate::file_tree::GetFileTreeParams;
+use crate::file_tree::{CustomValue, GetFileTreeParams};
+use crate::mailmap::Mailmap;
+use crate::repositories::{CloseRepositoryParams, RepoHandle, Repositories};
 
 #[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct OpenRepositoryParams {
     pub path: String,
+
+    /// Revision to read `.mailmap` from; `HEAD` if omitted.
+    #[serde(rename = "mailmapRev", skip_serializing_if = "Option::is_none")]
+    pub mailmap_rev: Option<String>,
+
+    /// Regular expressions matched case-insensitively against author names and
+    /// emails to detect bots, in addition to the built-in ones.
+    #[serde(rename = "botPatterns", skip_serializing_if = "Option::is_none")]
+    pub bot_patterns: Option<Vec<String>>,
 }
 
 #[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct OpenRepositoryResult {
     pub success: bool,
+    pub handle: RepoHandle,
 }
 
 #[cfg_attr(feature = "bindings", derive(Type))]
@@ -51,9 +69,28 @@ pub struct InitialDataResult {
     pub tags: Vec<String>,
 }
 
+pub type RequestId = u64;
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct Request {
+    /// Client-supplied correlation id, echoed on every response of this request and
+    /// named by `cancel`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<RequestId>,
+
+    /// Repository the request operates on; the default repository if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<RepoHandle>,
+
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 #[serde(tag = "method", content = "params")]
-pub enum Request {
+pub enum Command {
     #[serde(rename = "get_commits_for_time_range")]
     GetCommitsForTimeRange(GetCommitsForTimeRangeParams),
 
@@ -64,7 +101,7 @@ pub enum Request {
     StreamAuthors(StreamAuthorsParams),
 
     #[serde(rename = "get_authors")]
-    GetAuthors(NoParams),    
+    GetAuthors(GetAuthorsParams),
 
     #[serde(rename = "get_file_tree")]
     GetFileTree(GetFileTreeParams),
@@ -101,6 +138,24 @@ pub enum Request {
 
     #[serde(rename = "get_commit")]
     GetCommit(GetCommitParams),
+
+    #[serde(rename = "cancel")]
+    Cancel(CancelParams),
+
+    #[serde(rename = "get_capabilities")]
+    GetCapabilities(NoParams),
+
+    #[serde(rename = "close_repository")]
+    CloseRepository(CloseRepositoryParams),
+
+    #[serde(rename = "list_repositories")]
+    ListRepositories(NoParams),
+
+    #[serde(rename = "get_diff")]
+    GetDiff(GetDiffParams),
+
+    #[serde(rename = "map_legacy_author_ids")]
+    MapLegacyAuthorIds(MapLegacyAuthorIdsParams),
 }
 
 #[cfg_attr(feature = "bindings", derive(Type))]
@@ -111,29 +166,92 @@ fn is_false(b: &bool) -> bool {
     !(*b)
 }
 
+#[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct ErrorResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<RequestId>,
     error: String,
+    code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git: Option<GitErrorInfo>,
 }
 
+#[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, serde::Serialize)]
 pub struct DataResponse<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<RequestId>,
     data: T,
     #[serde(skip_serializing_if = "is_false")]
     end: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    cancelled: bool,
 }
 
+#[cfg_attr(feature = "bindings", derive(Type))]
 #[derive(Debug, serde::Serialize)]
 #[serde(untagged)]
 pub enum Response {
     Error(ErrorResponse),
-    Data(DataResponse<serde_json::Value>),
+    Data(DataResponse<CustomValue>),
+}
+
+impl Request {
+    /// Best-effort extraction of the request id from a payload that failed to deserialize,
+    /// so that the error can still be routed back to the caller.
+    pub fn peek_id(data: &str) -> Option<RequestId> {
+        let value: serde_json::Value = serde_json::from_str(data).ok()?;
+        value.get("id")?.as_u64()
+    }
+}
+
+impl Response {
+    pub fn data<T: Serialize>(id: Option<RequestId>, data: T, end: bool) -> Response {
+        Response::Data(DataResponse {
+            id,
+            data: serde_json::to_value(data).unwrap().into(),
+            end,
+            cancelled: false,
+        })
+    }
+
+    pub fn error(id: Option<RequestId>, err: &ExplorerError) -> Response {
+        Response::Error(ErrorResponse {
+            id,
+            error: err.to_string(),
+            code: err.code(),
+            git: err.git_info(),
+        })
+    }
+
+    pub fn id(&self) -> Option<RequestId> {
+        match self {
+            Response::Error(e) => e.id,
+            Response::Data(d) => d.id,
+        }
+    }
 }
 
 pub type ExplorerCallback = fn(Response) -> ();
 
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("panic: {}", message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("panic: {}", message)
+    } else {
+        "panic: unknown cause".to_string()
+    }
+}
+
 pub struct Explorer {
-    pub repo: Option<Repository>,
+    pub(crate) repositories: Repositories,
+    pub(crate) request_repo: Option<RepoHandle>,
+    pub(crate) request_id: Option<RequestId>,
+    pub(crate) cancellations: CancellationRegistry,
+    pub(crate) cancellation_poll: Option<CancellationPoll>,
+    pub(crate) concurrent_requests: u32,
     callback: Box<dyn Fn(Response) + Send + Sync>,
     pub shutdown: AtomicBool,
 }
@@ -149,29 +267,61 @@ impl Default for Explorer {
 impl Explorer {
     pub fn new() -> Explorer {
         Explorer {
-            repo: None,
+            repositories: Repositories::default(),
+            request_repo: None,
+            request_id: None,
+            cancellations: CancellationRegistry::new(),
+            cancellation_poll: None,
+            concurrent_requests: 1,
             shutdown: AtomicBool::new(false),
             callback: Box::new(|_| {}),
         }
     }
 
-    pub fn set_repo(&mut self, repo: Repository) {
-        self.repo.replace(repo);
+    /// Adds an already opened repository and makes it the default one, reading
+    /// its `.mailmap` at `HEAD` and detecting bots with the built-in patterns.
+    pub fn set_repo(&mut self, path: &str, repo: Repository) -> RepoHandle {
+        let mailmap = Mailmap::load(&repo, "HEAD").unwrap_or_default();
+        self.repositories
+            .insert(path.to_string(), repo, mailmap, BotDetector::default())
+    }
+
+    /// The repository addressed by the request currently being handled.
+    pub fn repo(&self) -> ExplorerResult<&Repository> {
+        self.repositories.get(self.request_repo)
+    }
+
+    /// The `.mailmap` of the repository addressed by the current request.
+    pub(crate) fn mailmap(&self) -> ExplorerResult<&Mailmap> {
+        self.repositories.get_mailmap(self.request_repo)
+    }
+
+    pub fn repo_mut(&mut self) -> ExplorerResult<&mut Repository> {
+        self.repositories.get_mut(self.request_repo)
     }
 
     pub fn send<T: Serialize>(&self, data: T, end: bool) {
         let callback = self.callback.as_ref();
 
+        callback(Response::data(self.request_id, data, end));
+    }
+
+    /// Terminates the stream of the current request after it has been cancelled.
+    pub fn send_cancelled(&self) {
+        let callback = self.callback.as_ref();
+
         callback(Response::Data(DataResponse {
-            data: serde_json::to_value(data).unwrap(),
-            end,
+            id: self.request_id,
+            data: serde_json::Value::Null.into(),
+            end: true,
+            cancelled: true,
         }));
     }
 
-    pub fn send_error(&self, message: String) {
+    pub fn send_error(&self, err: ExplorerError) {
         let callback = self.callback.as_ref();
 
-        callback(Response::Error(ErrorResponse { error: message }));
+        callback(Response::error(self.request_id, &err));
     }
 
     pub fn deserialize_request(data: String) -> Result<Request, serde_json::Error> {
@@ -183,73 +333,99 @@ impl Explorer {
     }
 
     pub fn cmd_open_repository(&mut self, params: &OpenRepositoryParams) {
-        match Repository::open(&params.path) {
-            Ok(repo) => {
-                self.repo.replace(repo);
-                self.send(OpenRepositoryResult { success: true }, true);
+        match self.open_repository(params) {
+            Ok(handle) => {
+                self.send(
+                    OpenRepositoryResult {
+                        success: true,
+                        handle,
+                    },
+                    true,
+                );
             }
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         };
     }
 
+    fn open_repository(&mut self, params: &OpenRepositoryParams) -> ExplorerResult<RepoHandle> {
+        let repo = Repository::open(&params.path)
+            .map_err(|e| ExplorerError::from_path_lookup(&params.path, e))?;
+
+        let mailmap = match &params.mailmap_rev {
+            Some(rev) => Mailmap::load(&repo, rev)?,
+            // an unborn HEAD has no mailmap yet
+            None => Mailmap::load(&repo, "HEAD").unwrap_or_default(),
+        };
+        let bots = BotDetector::new(params.bot_patterns.as_deref().unwrap_or_default())?;
+
+        Ok(self
+            .repositories
+            .insert(params.path.clone(), repo, mailmap, bots))
+    }
+
     pub fn cmd_get_initial_data(&self) {
-        let repo = self.repo.as_ref().unwrap();
+        match self.get_initial_data() {
+            Ok(data) => {
+                self.send(data, true);
+            }
+            Err(e) => {
+                self.send_error(e);
+            }
+        }
+    }
+
+    fn get_initial_data(&self) -> ExplorerResult<InitialDataResult> {
+        let repo = self.repo()?;
 
         // get current branch and files of last commit
 
-        let head = repo.head().unwrap();
-        let head_commit = head.peel_to_commit().unwrap();
+        let head = repo.head()?;
+        let head_commit = head.peel_to_commit()?;
         let head_commit_id = head_commit.id().to_string();
 
-        let remote_names = repo.remotes().unwrap();
-        let remote_names: Vec<String> = remote_names
-            .iter()
-            .map(|r| r.unwrap().to_string())
-            .collect();
+        let remote_names = repo.remotes()?;
+        let remote_names: Vec<String> = remote_names.iter().flatten().map(String::from).collect();
 
-        let remotes_raw: Vec<git2::Remote> = remote_names
-            .iter()
-            .map(|r| repo.find_remote(r).unwrap())
-            .collect();
+        let mut remotes_raw: Vec<git2::Remote> = Vec::new();
+        for name in remote_names.iter() {
+            remotes_raw.push(repo.find_remote(name)?);
+        }
 
-        let tags = repo.tag_names(None).unwrap();
-        let tags = tags.iter().map(|t| t.unwrap().to_string()).collect();
+        let tags = repo.tag_names(None)?;
+        let tags = tags.iter().flatten().map(String::from).collect();
 
-        let branches = repo.branches(Some(BranchType::Local)).unwrap();
-        let branches = branches
-            .map(|b| b.unwrap())
-            .map(|(b, _)| b.name().unwrap().unwrap().to_string())
-            .collect();
+        let mut branches = Vec::new();
+        for branch in repo.branches(Some(BranchType::Local))? {
+            let (branch, _) = branch?;
+            if let Some(name) = branch.name()? {
+                branches.push(name.to_string());
+            }
+        }
 
         let mut remotes: Vec<Remote> = Vec::new();
 
         for remote in remotes_raw {
-            let name = remote.name().unwrap().to_string();
-            let url = remote.url().unwrap().to_string();
+            let name = remote.name().unwrap_or_default().to_string();
+            let url = remote.url().unwrap_or_default().to_string();
 
             remotes.push(Remote { name, url });
         }
         
-        let mut revwalk = repo.revwalk().unwrap();
-        revwalk.push(head_commit.id()).unwrap();
-        revwalk.set_sorting(git2::Sort::TIME).unwrap();
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(head_commit.id())?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
 
         let mut first_commit: Option<git2::Commit> = None;
 
         for oid in revwalk {
-            if oid.is_err() {
+            let Ok(oid) = oid else {
                 continue;
-            }
-            let oid = oid.unwrap();
-            let commit = repo.find_commit(oid);
-
-            if commit.is_err() {
+            };
+            let Ok(commit) = repo.find_commit(oid) else {
                 continue;
-            }
-
-            let commit = commit.unwrap();
+            };
 
             if commit.parent_count() == 0 {
                 first_commit = Some(commit);
@@ -257,49 +433,143 @@ impl Explorer {
             }
         }
 
-        if first_commit.is_none() {
-            self.send_error("No initial commit found".to_string());
-            return;
-        }
-       
-        let first_commit_id = first_commit.unwrap().id().to_string();
+        let first_commit_id = match first_commit {
+            Some(commit) => commit.id().to_string(),
+            None => return Err(ExplorerError::Internal("No initial commit found".to_string())),
+        };
 
-        let data = InitialDataResult {
-            current_branch: head.shorthand().unwrap().to_string(),
-            last_commit: self.get_commit(&head_commit_id).unwrap(),
-            first_commit: self.get_commit(&first_commit_id).unwrap(),
+        Ok(InitialDataResult {
+            current_branch: head.shorthand().unwrap_or_default().to_string(),
+            last_commit: self.get_commit(&head_commit_id, &CommitOptions::default())?,
+            first_commit: self.get_commit(&first_commit_id, &CommitOptions::default())?,
             remotes,
             branches,
             tags,
-        };
-
-        self.send(data, true);
+        })
     }
 
+    /// Runs `request` to completion, reporting its responses to `cb`.
+    ///
+    /// An explorer handles one request at a time, so the responses of different
+    /// requests never interleave. Hosts run requests concurrently by spreading them
+    /// over several explorers, and report how many as `concurrentRequests`.
     pub fn handle(&mut self, request: Request, cb: impl Fn(Response) + Send + Sync + 'static) {
         self.callback = Box::new(cb);
+        self.request_id = request.id;
+        self.request_repo = request.repo;
 
-        match request {
-            Request::OpenRepository(params) => self.cmd_open_repository(&params),
-            Request::StreamAuthors(_) => self.cmd_stream_authors(),
-            Request::GetAuthors(_) => self.cmd_get_authors(),
-            Request::GetFileTree(params) => self.get_file_tree(&params),
-            Request::StreamFileTree(params) => self.stream_file_tree(&params),
-            Request::GetBranchList(_) => self.cmd_get_branches(),
-            Request::GetGitGraph(_) => self.cmd_get_git_graph(),
-            Request::GetBlame(params) => self.cmd_get_blame(&params),
-            Request::GetFileContent(params) => self.get_file_content(&params),
-            Request::GetCommitsForBranch(params) => self.cmd_get_commits_for_branch(&params),
-            Request::StreamCommits(_) => self.cmd_stream_commits(),
-            Request::GetInitialData(_) => self.cmd_get_initial_data(),
-            Request::GetCommitsForTimeRange(params) => self.cmd_get_commits_for_time_range(params),
-            Request::IsValidRev(params) => self.cmd_is_valid_rev(&params),
-            Request::GetCommit(params) => self.cmd_get_commit(&params),
-            Request::Shutdown(_) => {
+        if let Some(id) = self.request_id {
+            self.cancellations.register(id);
+        }
+
+        if self.is_cancelled() {
+            self.send_cancelled();
+        } else {
+            // A panicking command must not take the explorer (and the opened repository) down
+            // with it. Only native hosts are covered, as they unwind: explorer-web is built for
+            // wasm32-wasi, which aborts on panic, so there a panic still ends the worker.
+            let result = panic::catch_unwind(AssertUnwindSafe(|| self.dispatch(request.command)));
+
+            if let Err(payload) = result {
+                self.send_error(ExplorerError::Internal(panic_message(payload.as_ref())));
+            }
+        }
+
+        if let Some(id) = self.request_id {
+            self.cancellations.clear(id);
+        }
+
+        self.callback = Box::new(|_| {});
+        self.request_id = None;
+        self.request_repo = None;
+    }
+
+    fn dispatch(&mut self, command: Command) {
+        match command {
+            Command::OpenRepository(params) => self.cmd_open_repository(&params),
+            Command::StreamAuthors(params) => self.cmd_stream_authors(&params),
+            Command::GetAuthors(params) => self.cmd_get_authors(&params),
+            Command::GetFileTree(params) => self.get_file_tree(&params),
+            Command::StreamFileTree(params) => self.stream_file_tree(&params),
+            Command::GetBranchList(_) => self.cmd_get_branches(),
+            Command::GetGitGraph(_) => self.cmd_get_git_graph(),
+            Command::GetBlame(params) => self.cmd_get_blame(&params),
+            Command::GetFileContent(params) => self.get_file_content(&params),
+            Command::GetCommitsForBranch(params) => self.cmd_get_commits_for_branch(&params),
+            Command::StreamCommits(params) => self.cmd_stream_commits(&params),
+            Command::GetInitialData(_) => self.cmd_get_initial_data(),
+            Command::GetCommitsForTimeRange(params) => self.cmd_get_commits_for_time_range(params),
+            Command::IsValidRev(params) => self.cmd_is_valid_rev(&params),
+            Command::GetCommit(params) => self.cmd_get_commit(&params),
+            Command::Cancel(params) => self.cmd_cancel(&params),
+            Command::GetCapabilities(_) => self.cmd_get_capabilities(),
+            Command::CloseRepository(params) => self.cmd_close_repository(&params),
+            Command::ListRepositories(_) => self.cmd_list_repositories(),
+            Command::GetDiff(params) => self.cmd_get_diff(&params),
+            Command::MapLegacyAuthorIds(params) => self.cmd_map_legacy_author_ids(&params),
+            Command::Shutdown(_) => {
                 self.shutdown.store(true, Ordering::Relaxed);
             }
         };
+    }
+}
 
-        self.callback = Box::new(|_| {});
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    fn get_branches(id: RequestId) -> Request {
+        let message = format!(r#"{{"id":{},"method":"get_branches","params":{{}}}}"#, id);
+        Explorer::deserialize_request(message).unwrap()
+    }
+
+    #[test]
+    fn survives_a_panicking_request() {
+        let repo = TestRepo::new("panic");
+        repo.commit("first").file("a.txt", "a\n").create();
+        let mut explorer = repo.explorer();
+
+        let responses = Arc::new(Mutex::new(Vec::new()));
+
+        // the callback panics on the first response, which is sent while the command runs
+        let calls = AtomicUsize::new(0);
+        let seen = responses.clone();
+        explorer.handle(get_branches(1), move |response| {
+            if calls.fetch_add(1, Ordering::Relaxed) == 0 {
+                panic!("callback failed");
+            }
+            seen.lock()
+                .unwrap()
+                .push(serde_json::to_value(&response).unwrap());
+        });
+
+        let seen = responses.clone();
+        explorer.handle(get_branches(2), move |response| {
+            seen.lock()
+                .unwrap()
+                .push(serde_json::to_value(&response).unwrap());
+        });
+
+        let responses = responses.lock().unwrap();
+        assert_eq!(responses.len(), 2);
+
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(
+            responses[0]["code"],
+            serde_json::to_value(ErrorCode::Internal).unwrap()
+        );
+        assert_eq!(
+            responses[0]["error"],
+            "Internal error: panic: callback failed"
+        );
+
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["data"], Value::from(vec!["main"]));
     }
 }
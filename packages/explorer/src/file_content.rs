@@ -6,6 +6,7 @@ use std::path::Path;
 #[cfg(feature = "bindings")]
 use specta::Type;
 
+use crate::error::{ExplorerError, ExplorerResult};
 use crate::explorer::Explorer;
 use base64::prelude::*;
 
@@ -67,8 +68,7 @@ pub fn get_image_mime_type(t: &ImageType) -> &str {
 
 pub fn get_image_type_from_path(path: &Path) -> Option<&ImageType> {
     if let Some(extension) = Path::new(path).extension() {
-        let extension = extension.to_str().unwrap();
-        return get_image_type(extension);
+        return get_image_type(extension.to_str()?);
     }
 
     None
@@ -77,14 +77,18 @@ pub fn get_image_type_from_path(path: &Path) -> Option<&ImageType> {
 pub fn get_file_content(
     params: &GetFileContentParams,
     repo: &Repository,
-) -> Result<GetFileContentResult, git2::Error> {
+) -> ExplorerResult<GetFileContentResult> {
     let rev = params.rev.clone();
-    let commit_id = repo.revparse_single(rev.as_str())?.id();
-    let commit = repo.find_commit(commit_id)?;
+    let commit = repo
+        .revparse_single(rev.as_str())
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| ExplorerError::from_rev_lookup(&rev, e))?;
    
     let tree = commit.tree()?;
     let path = Path::new(params.path.as_str());
-    let entry = tree.get_path(path)?;
+    let entry = tree
+        .get_path(path)
+        .map_err(|e| ExplorerError::from_path_lookup(&params.path, e))?;
 
     if entry.kind() != Some(ObjectType::Blob) {
         return Ok(GetFileContentResult {
@@ -104,17 +108,17 @@ pub fn get_file_content(
 
     let image_type = get_image_type_from_path(&path);
 
-    if image_type.is_none() || is_lfs {
+    let Some(image_type) = image_type.filter(|_| !is_lfs) else {
         let content_str = String::from_utf8_lossy(content).to_string();
         return Ok(GetFileContentResult {
             content: content_str,
             encoding: "utf-8".to_string(),
             lfs: Some(is_lfs),
         });
-    }
+    };
 
 
-    let mime_type = get_image_mime_type(image_type.unwrap()); 
+    let mime_type = get_image_mime_type(image_type);
 
     let base64_data = BASE64_STANDARD.encode(content);
     
@@ -132,16 +136,16 @@ pub fn get_file_content(
 
 impl Explorer {
     pub fn get_file_content(&self, params: &GetFileContentParams) {
-        let repo = self.repo.as_ref().unwrap();
-
-        let result = get_file_content(params, repo);
+        let result = self
+            .repo()
+            .and_then(|repo| get_file_content(params, repo));
 
         match result {
             Ok(content) => {
                 self.send(content, true);
             }
             Err(err) => {
-                self.send_error(err.message().to_string());
+                self.send_error(err);
             }
         }
     }
@@ -1,10 +1,11 @@
-use git2::{Error, ObjectType, Repository, Tree};
+use git2::{ObjectType, Repository, Tree};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 #[cfg(feature = "bindings")]
 use specta::Type;
 
+use crate::error::ExplorerResult;
 use crate::explorer::Explorer;
 use crate::file_types::get_file_type;
 
@@ -24,6 +25,12 @@ impl specta::Type for CustomValue {
     }
 }
 
+impl From<Value> for CustomValue {
+    fn from(value: Value) -> Self {
+        CustomValue(value)
+    }
+}
+
 impl From<u32> for CustomValue {
     fn from(value: u32) -> Self {
         CustomValue(Value::Number(value.into()))
@@ -60,13 +67,17 @@ impl Explorer {
         stream: bool,
         tree: &Tree,
         prefix: Vec<String>,
-    ) -> Result<Vec<FileTreeNode>, Error> {
+    ) -> ExplorerResult<Vec<FileTreeNode>> {
         let mut result: Vec<FileTreeNode> = Vec::new();
 
         let mut sub_trees: Vec<(Vec<String>, Tree)> = Vec::new();
 
         for entry in tree.iter() {
-            let entry_name = entry.name().unwrap().to_string();
+            if self.is_cancelled() {
+                return Ok(result);
+            }
+
+            let entry_name = String::from_utf8_lossy(entry.name_bytes()).to_string();
 
             let path = {
                 let mut path = prefix.clone();
@@ -106,6 +117,10 @@ impl Explorer {
         }
 
         for (path, subtree) in sub_trees {
+            if self.is_cancelled() {
+                return Ok(result);
+            }
+
             let sub_results = self.traverse_tree(repo, stream, &subtree, path);
 
             if !stream {
@@ -134,8 +149,8 @@ impl Explorer {
         &self,
         params: &GetFileTreeParams,
         stream: bool,
-    ) -> Result<Vec<FileTreeNode>, git2::Error> {
-        let repo = self.repo.as_ref().unwrap();
+    ) -> ExplorerResult<Vec<FileTreeNode>> {
+        let repo = self.repo()?;
 
         let rev = params.rev.clone();
         let commit_id = self.get_commit_oid_from_rev(rev.as_str())?;
@@ -150,11 +165,14 @@ impl Explorer {
         let result = self.file_tree(params, true);
 
         match result {
+            Ok(_) if self.is_cancelled() => {
+                self.send_cancelled();
+            }
             Ok(_) => {
                 self.send("", true);
             }
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
@@ -163,11 +181,14 @@ impl Explorer {
         let result = self.file_tree(params, false);
 
         match result {
+            Ok(_) if self.is_cancelled() => {
+                self.send_cancelled();
+            }
             Ok(tree) => {
                 self.send(tree, true);
             }
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
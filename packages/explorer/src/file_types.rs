@@ -33,5 +33,5 @@ pub fn get_file_type(filename: &str) -> u32 {
             break;
         }
     }
-    file_type.try_into().unwrap()
+    file_type.try_into().unwrap_or(u32::MAX)
 }
@@ -1,4 +1,4 @@
-use git2::{Error, Repository};
+use git2::Repository;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "bindings")]
@@ -12,7 +12,7 @@ use std::{
 use petgraph::graph::DiGraph;
 use std::fmt;
 
-use crate::{explorer::Explorer, utils::get_author_id};
+use crate::{error::ExplorerResult, explorer::Explorer};
 
 type Oid = String;
 type Aid = String;
@@ -78,8 +78,8 @@ pub struct CommitTree {
 
 impl Explorer {
 
-    pub fn get_stash_ids(&mut self) -> Result<HashSet<Oid>, Error> {
-        let  repo = self.repo.as_mut().unwrap();
+    pub fn get_stash_ids(&mut self) -> ExplorerResult<HashSet<Oid>> {
+        let repo = self.repo_mut()?;
 
         let mut stashes = HashSet::new();
         repo.stash_foreach(|_, _, oid| {
@@ -100,16 +100,17 @@ impl Explorer {
                 self.send(graph, true);
             }
             Err(e) => {
-                self.send_error(e.message().to_string());
+                self.send_error(e);
             }
         }
     }
 
 
-     fn get_git_graph(&mut self) -> Result<CommitTree, git2::Error> {
+     fn get_git_graph(&mut self) -> ExplorerResult<CommitTree> {
         let stashes: HashSet<String> = self.get_stash_ids()?;
 
-        let repo: &Repository = self.repo.as_ref().unwrap();
+        let repo: &Repository = self.repo()?;
+        let mailmap = self.mailmap()?;
 
         let mut walk = repo.revwalk()?;
     
@@ -120,12 +121,10 @@ impl Explorer {
         let mut commit_infos = Vec::new();
         let mut commit_indices = HashMap::new();
     
-        for _oid in walk {
-            if _oid.is_err() {
+        for oid in walk {
+            let Ok(oid) = oid else {
                 continue;
-            }
-    
-            let oid = _oid.unwrap();
+            };
             let oid_str = to_string_oid(&oid);
             if stashes.contains(&oid_str) {
                 continue;
@@ -139,21 +138,19 @@ impl Explorer {
             }
             let author = commit.author();
     
-            let author_name = author.name().unwrap().to_string();
-            let author_email = author.email().unwrap().to_string();
+            let author_name = String::from_utf8_lossy(author.name_bytes()).to_string();
+            let author_email = String::from_utf8_lossy(author.email_bytes()).to_string();
     
-            let author_id = get_author_id(&author_name, &author_email);
+            let author_id = mailmap.author_id(&author_name, &author_email);
     
-            let mut message = commit.message().unwrap().to_string();
+            let mut message = String::from_utf8_lossy(commit.message_bytes()).to_string();
     
-            let char_indices = message.char_indices();
             // ensure message is max 120 chars and only one line
-            if char_indices.count() > 120 {
-                let slice_position = message.char_indices().nth(120).unwrap().0;
-                message = message[0..slice_position].to_string();
+            if let Some((slice_position, _)) = message.char_indices().nth(120) {
+                message.truncate(slice_position);
             }
-            if message.contains('\n') {
-                message = message[0..message.find('\n').unwrap()].to_string();
+            if let Some(newline) = message.find('\n') {
+                message.truncate(newline);
             }
     
             let commit_info = GitGraphCommitInfo {
@@ -172,16 +169,15 @@ impl Explorer {
         }
     
         for i in 1..commit_infos.len() {
-            let commit = commit_infos.get(i).unwrap();
+            let commit = &commit_infos[i];
             let commit_oid = commit.oid.clone();
             let parent_ids = commit.parents.clone();
     
-            for parent_id in parent_ids.iter() {
-                if parent_id.is_none() {
+            for parent_oid in parent_ids.iter().flatten() {
+                // parents may be missing from the walk (e.g. stashes or shallow clones)
+                let Some(parent_index) = commit_indices.get(parent_oid.as_str()) else {
                     continue;
-                }
-                let parent_oid = parent_id.as_ref().unwrap();
-                let parent_index = commit_indices.get(parent_oid.as_str()).unwrap();
+                };
                 commit_infos[*parent_index]
                     .children
                     .push(commit_oid.clone());
@@ -194,8 +190,12 @@ impl Explorer {
     
         for branch in braches_list {
             let (branch, _) = branch?;
-            let branch_name = branch.name()?.unwrap().to_string();
-            let branch_oid = branch.get().target().unwrap().to_string();
+            let (Some(branch_name), Some(branch_oid)) = (branch.name()?, branch.get().target())
+            else {
+                continue;
+            };
+            let branch_name = branch_name.to_string();
+            let branch_oid = branch_oid.to_string();
             let branch_info = BranchInfo {
                 id: branch_oid.clone(),
                 name: branch_name.clone(),
@@ -204,16 +204,6 @@ impl Explorer {
             branches.push(branch_info);
         }
     
-        // create a string which contains a dot for each branch which exists
-    
-        let mut branch_dots = String::new();
-        for _ in 0..branches.len() {
-            branch_dots = branch_dots.add(" .");
-        }
-    
-        #[cfg(not(target_arch = "wasm32"))]
-        println!("   {}", branch_dots);
-    
         for (_i, commit) in commit_infos.iter().enumerate() {
             let _num_parents = commit.parents.iter().filter(|x| x.is_some()).count();
     
@@ -245,14 +235,12 @@ impl Explorer {
         for (_i, commit) in commit_infos.iter().enumerate() {
             let commit_oid = commit.oid.clone();
     
-            for parent in commit.parents.iter() {
-                if parent.is_none() {
-                    continue;
+            for parent_oid in commit.parents.iter().flatten() {
+                if let (Some(parent_idx), Some(child_idx)) =
+                    (commit_nodes.get(parent_oid), commit_nodes.get(&commit_oid))
+                {
+                    graph.add_edge(*parent_idx, *child_idx, 1);
                 }
-                let parent_oid = parent.as_ref().unwrap();
-                let parent_idx = commit_nodes.get(parent_oid).unwrap();
-                let child_idx = commit_nodes.get(&commit_oid).unwrap();
-                graph.add_edge(*parent_idx, *child_idx, 1);
             }
         }
     
@@ -5,18 +5,45 @@ extern crate lazy_static;
 
 mod authors;
 mod blame;
+mod bots;
 mod branches;
+mod cancellation;
+mod capabilities;
 mod commits;
+mod diff;
+mod error;
 mod file_content;
 mod explorer;
 mod file_tree;
 mod file_types;
 mod file_types_db;
 mod git_graph;
+#[cfg(feature = "server")]
+pub mod http;
+mod mailmap;
+mod message;
+mod pagination;
+mod protocol;
+mod repositories;
+#[cfg(test)]
+mod test_repo;
+pub mod transport;
 mod utils;
+#[cfg(feature = "server")]
+pub mod websocket;
 
 pub use explorer::Explorer as Explorer;
 pub use explorer::ExplorerCallback as ExplorerCallback;
 pub use explorer::Request as Request;
+pub use explorer::RequestId as RequestId;
+pub use explorer::Command as Command;
+pub use cancellation::CancellationRegistry as CancellationRegistry;
+pub use capabilities::Capabilities as Capabilities;
+pub use capabilities::PROTOCOL_VERSION as PROTOCOL_VERSION;
+pub use error::ErrorCode as ErrorCode;
+pub use error::ExplorerError as ExplorerError;
 pub use explorer::Response as Response;
-pub use explorer::OpenRepositoryParams as OpenRepositoryParams;
\ No newline at end of file
+pub use explorer::OpenRepositoryParams as OpenRepositoryParams;
+pub use protocol::MethodInfo as MethodInfo;
+pub use protocol::METHODS as METHODS;
+pub use repositories::RepoHandle as RepoHandle;
\ No newline at end of file
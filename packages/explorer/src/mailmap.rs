@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use git2::{ErrorCode, Repository};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bindings")]
+use specta::Type;
+
+use crate::error::{ExplorerError, ExplorerResult};
+use crate::utils;
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+/// Replacement for one commit email; either part may be left as committed.
+#[derive(Debug, Default, Clone)]
+struct Replacement {
+    name: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    /// Applies to every name committed with this email.
+    any_name: Option<Replacement>,
+
+    /// Applies to a single committed name, keyed lowercased.
+    by_name: HashMap<String, Replacement>,
+}
+
+/// Canonical author identities read from a `.mailmap` file, see gitmailmap(5).
+///
+/// git2's own mailmap can't be shared across the worker threads an explorer is
+/// moved between, so the format is parsed here.
+#[derive(Debug, Default)]
+pub struct Mailmap {
+    /// Keyed by the lowercased commit email.
+    entries: HashMap<String, Entry>,
+}
+
+impl Mailmap {
+    /// Reads `.mailmap` from the tree of `rev`; an empty mailmap if there is none.
+    pub fn load(repo: &Repository, rev: &str) -> ExplorerResult<Mailmap> {
+        let object = repo
+            .revparse_single(rev)
+            .map_err(|e| ExplorerError::from_rev_lookup(rev, e))?;
+        let tree = object.peel_to_tree()?;
+
+        let entry = match tree.get_path(std::path::Path::new(".mailmap")) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(Mailmap::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let blob = entry.to_object(repo)?.peel_to_blob()?;
+
+        Ok(Mailmap::parse(&String::from_utf8_lossy(blob.content())))
+    }
+
+    pub fn parse(text: &str) -> Mailmap {
+        let mut mailmap = Mailmap::default();
+
+        for line in text.lines() {
+            let line = match line.find('#') {
+                Some(comment) => &line[..comment],
+                None => line,
+            };
+
+            let Some((first_name, first_email, rest)) = split_identity(line) else {
+                continue;
+            };
+
+            match split_identity(rest) {
+                // Proper Name <proper@email> [Commit Name] <commit@email>
+                Some((commit_name, commit_email, _)) => mailmap.add(
+                    Replacement {
+                        name: first_name.map(str::to_string),
+                        email: Some(first_email.to_string()),
+                    },
+                    commit_name,
+                    commit_email,
+                ),
+                // Proper Name <commit@email>
+                None => mailmap.add(
+                    Replacement {
+                        name: first_name.map(str::to_string),
+                        email: None,
+                    },
+                    None,
+                    first_email,
+                ),
+            }
+        }
+
+        mailmap
+    }
+
+    fn add(&mut self, replacement: Replacement, commit_name: Option<&str>, commit_email: &str) {
+        let entry = self.entries.entry(commit_email.to_lowercase()).or_default();
+
+        let existing = match commit_name {
+            Some(commit_name) => entry.by_name.entry(commit_name.to_lowercase()).or_default(),
+            None => entry.any_name.get_or_insert_with(Replacement::default),
+        };
+
+        // later lines only override the parts they specify, like git
+        if replacement.name.is_some() {
+            existing.name = replacement.name;
+        }
+        if replacement.email.is_some() {
+            existing.email = replacement.email;
+        }
+    }
+
+    /// The canonical identity of a commit author; the identity itself if it is not mapped.
+    pub fn resolve(&self, name: &str, email: &str) -> Identity {
+        let replacement = self.entries.get(&email.to_lowercase()).and_then(|entry| {
+            entry
+                .by_name
+                .get(&name.to_lowercase())
+                .or(entry.any_name.as_ref())
+        });
+
+        Identity {
+            name: replacement
+                .and_then(|r| r.name.clone())
+                .unwrap_or_else(|| name.to_string()),
+            email: replacement
+                .and_then(|r| r.email.clone())
+                .unwrap_or_else(|| email.to_string()),
+        }
+    }
+
+    /// Author id of the canonical identity, so that all aliases share one id.
+    pub fn author_id(&self, name: &str, email: &str) -> String {
+        let identity = self.resolve(name, email);
+        utils::get_author_id(&identity.name, &identity.email)
+    }
+}
+
+/// Splits `Name <email> rest` into the trimmed name, if any, the email and the rest.
+fn split_identity(text: &str) -> Option<(Option<&str>, &str, &str)> {
+    let open = text.find('<')?;
+    let close = open + text[open..].find('>')?;
+
+    let name = text[..open].trim();
+    let name = if name.is_empty() { None } else { Some(name) };
+
+    Some((name, text[open + 1..close].trim(), &text[close + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Identity, Mailmap};
+
+    fn identity(name: &str, email: &str) -> Identity {
+        Identity {
+            name: name.to_string(),
+            email: email.to_string(),
+        }
+    }
+
+    #[test]
+    fn replaces_name_only() {
+        let mailmap = Mailmap::parse("Alice Liddell <alice@x.org>\n");
+
+        assert_eq!(
+            mailmap.resolve("alice", "alice@x.org"),
+            identity("Alice Liddell", "alice@x.org")
+        );
+    }
+
+    #[test]
+    fn replaces_email_only() {
+        let mailmap = Mailmap::parse("<alice@x.org> <alice@old.org>\n");
+
+        assert_eq!(
+            mailmap.resolve("alice", "alice@old.org"),
+            identity("alice", "alice@x.org")
+        );
+    }
+
+    #[test]
+    fn replaces_name_and_email() {
+        let mailmap = Mailmap::parse("Alice Liddell <alice@x.org> <alice@old.org>\n");
+
+        assert_eq!(
+            mailmap.resolve("alice", "alice@old.org"),
+            identity("Alice Liddell", "alice@x.org")
+        );
+    }
+
+    #[test]
+    fn replaces_by_commit_name_and_email() {
+        let mailmap = Mailmap::parse(
+            "Alice Liddell <alice@x.org> alice <shared@x.org>\n\
+             Bob Builder <bob@x.org> bob <shared@x.org>\n",
+        );
+
+        assert_eq!(
+            mailmap.resolve("Alice", "shared@x.org"),
+            identity("Alice Liddell", "alice@x.org")
+        );
+        assert_eq!(
+            mailmap.resolve("bob", "shared@x.org"),
+            identity("Bob Builder", "bob@x.org")
+        );
+        // other names committed with the email are left alone
+        assert_eq!(
+            mailmap.resolve("carol", "shared@x.org"),
+            identity("carol", "shared@x.org")
+        );
+    }
+
+    #[test]
+    fn ignores_comments_and_blank_lines() {
+        let mailmap = Mailmap::parse(
+            "# Alice Liddell <alice@x.org>\n\
+             \n\
+             Bob Builder <bob@x.org> # the one who builds\n\
+             not an entry\n",
+        );
+
+        assert_eq!(
+            mailmap.resolve("alice", "alice@x.org"),
+            identity("alice", "alice@x.org")
+        );
+        assert_eq!(
+            mailmap.resolve("bob", "bob@x.org"),
+            identity("Bob Builder", "bob@x.org")
+        );
+    }
+
+    #[test]
+    fn matches_emails_case_insensitively() {
+        let mailmap = Mailmap::parse("Alice Liddell <alice@x.org> <Alice@Old.org>\n");
+
+        assert_eq!(
+            mailmap.resolve("alice", "ALICE@old.ORG"),
+            identity("Alice Liddell", "alice@x.org")
+        );
+    }
+
+    #[test]
+    fn later_lines_override_the_parts_they_specify() {
+        let mailmap = Mailmap::parse(
+            "Alice <alice@x.org> <alice@old.org>\n\
+             Alice Liddell <alice@old.org>\n",
+        );
+
+        assert_eq!(
+            mailmap.resolve("alice", "alice@old.org"),
+            identity("Alice Liddell", "alice@x.org")
+        );
+    }
+
+    #[test]
+    fn aliases_share_an_author_id() {
+        let mailmap = Mailmap::parse("Alice Liddell <alice@x.org> <alice@old.org>\n");
+
+        assert_eq!(
+            mailmap.author_id("alice", "alice@old.org"),
+            mailmap.author_id("Alice Liddell", "alice@x.org")
+        );
+        assert_ne!(
+            mailmap.author_id("alice", "alice@old.org"),
+            Mailmap::default().author_id("alice", "alice@old.org")
+        );
+    }
+}
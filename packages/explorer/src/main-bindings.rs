@@ -5,19 +5,34 @@ use specta::{ts::*, *};
 
 mod authors;
 mod blame;
+mod bots;
 mod branches;
+mod cancellation;
+mod capabilities;
 mod commits;
+mod diff;
+mod error;
 mod explorer;
 mod file_content;
 mod file_tree;
 mod file_types;
 mod file_types_db;
 mod git_graph;
+mod mailmap;
+mod message;
+mod pagination;
+mod protocol;
+mod repositories;
+#[cfg(test)]
+mod test_repo;
 mod utils;
 
 #[macro_use]
 extern crate lazy_static;
 
+use std::fs::OpenOptions;
+use std::io::Write;
+
 use structopt::StructOpt;
 #[derive(StructOpt)]
 struct Args {
@@ -34,6 +49,16 @@ pub fn main() {
         &ExportConfig::default().bigint(BigIntExportBehavior::Number),
     ) {
         Ok(_) => {
+            let maps = protocol::typescript_method_maps();
+            let written = OpenOptions::new()
+                .append(true)
+                .open(&args.output)
+                .and_then(|mut file| write!(file, "{}", maps));
+
+            if let Err(e) = written {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
             println!("Bindings generated successfully");
         }
         Err(e) => {
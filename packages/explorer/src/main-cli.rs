@@ -0,0 +1,255 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use explorer::{Explorer, Request};
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "explorer", about = "Explore a git repository from the terminal")]
+struct Args {
+    /// Path of the repository to open.
+    #[structopt(long, short, default_value = ".", parse(from_os_str))]
+    repo: PathBuf,
+
+    /// Print the raw protocol payloads as JSON, one document per line.
+    #[structopt(long)]
+    json: bool,
+
+    /// Like --json, but pretty-printed.
+    #[structopt(long)]
+    pretty: bool,
+
+    #[structopt(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Clone, Copy)]
+enum Output {
+    Text,
+    Json,
+    Pretty,
+}
+
+#[derive(StructOpt)]
+enum Cmd {
+    /// List all authors with their number of commits.
+    Authors,
+    /// Show line-by-line blame of a file.
+    Blame { rev: String, path: String },
+    /// List all files of a revision.
+    Tree { rev: String },
+    /// Stream all commits of the repository.
+    Commits,
+    /// Print the commit graph.
+    Graph,
+    /// Print the content of a file.
+    Content { rev: String, path: String },
+}
+
+impl Cmd {
+    fn request(&self) -> Value {
+        match self {
+            Cmd::Authors => json!({ "method": "get_authors", "params": { "order": "num_commits" } }),
+            Cmd::Blame { rev, path } => {
+                json!({ "method": "get_blame", "params": { "rev": rev, "path": path } })
+            }
+            Cmd::Tree { rev } => json!({ "method": "get_file_tree", "params": { "rev": rev } }),
+            Cmd::Commits => json!({ "method": "stream_commits", "params": {} }),
+            Cmd::Graph => json!({ "method": "get_git_graph", "params": {} }),
+            Cmd::Content { rev, path } => {
+                json!({ "method": "get_file_content", "params": { "rev": rev, "path": path } })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Authors,
+    Blame,
+    Tree,
+    Commits,
+    Graph,
+    Content,
+}
+
+impl From<&Cmd> for Kind {
+    fn from(cmd: &Cmd) -> Self {
+        match cmd {
+            Cmd::Authors => Kind::Authors,
+            Cmd::Blame { .. } => Kind::Blame,
+            Cmd::Tree { .. } => Kind::Tree,
+            Cmd::Commits => Kind::Commits,
+            Cmd::Graph => Kind::Graph,
+            Cmd::Content { .. } => Kind::Content,
+        }
+    }
+}
+
+fn short(oid: &Value) -> &str {
+    let oid = oid.as_str().unwrap_or_default();
+    &oid[..oid.len().min(8)]
+}
+
+fn first_line(message: &Value) -> &str {
+    message.as_str().unwrap_or_default().lines().next().unwrap_or_default()
+}
+
+fn print_text(out: &mut impl Write, kind: Kind, data: &Value) -> io::Result<()> {
+    match kind {
+        Kind::Authors => {
+            let authors = data.as_array().cloned().unwrap_or_default();
+            for author in authors {
+                writeln!(
+                    out,
+                    "{:>7}  {} <{}>",
+                    author["numCommits"],
+                    author["name"].as_str().unwrap_or_default(),
+                    author["email"].as_str().unwrap_or_default()
+                )?;
+            }
+        }
+        Kind::Blame => {
+            for line in data["lines"].as_array().into_iter().flatten() {
+                writeln!(
+                    out,
+                    "{} {:>5} {}",
+                    short(&line["commitId"]),
+                    line["lineNo"],
+                    line["content"].as_str().unwrap_or_default()
+                )?;
+            }
+        }
+        Kind::Tree => {
+            for node in data.as_array().into_iter().flatten() {
+                if node["kind"].is_number() {
+                    let path: Vec<&str> = node["path"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|p| p.as_str())
+                        .collect();
+                    writeln!(out, "{}", path.join("/"))?;
+                }
+            }
+        }
+        Kind::Commits => {
+            if data.is_object() {
+                writeln!(
+                    out,
+                    "{} {} {}",
+                    short(&data["oid"]),
+                    data["timestamp"].as_str().unwrap_or_default(),
+                    first_line(&data["message"])
+                )?;
+            }
+        }
+        Kind::Graph => {
+            for commit in data["graph"]["commits"].as_array().into_iter().flatten() {
+                let parents: Vec<&str> = commit["parents"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|p| !p.is_null())
+                    .map(short)
+                    .collect();
+                writeln!(
+                    out,
+                    "{} [{}] {}",
+                    short(&commit["oid"]),
+                    parents.join(" "),
+                    first_line(&commit["message"])
+                )?;
+            }
+        }
+        Kind::Content => {
+            write!(out, "{}", data["content"].as_str().unwrap_or_default())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn main() {
+    let args = Args::from_args();
+
+    let output = if args.pretty {
+        Output::Pretty
+    } else if args.json {
+        Output::Json
+    } else {
+        Output::Text
+    };
+
+    let mut explorer = Explorer::new();
+    let failed = Arc::new(AtomicBool::new(false));
+
+    let report = {
+        let failed = failed.clone();
+        move |response: Value| {
+            if let Some(error) = response.get("error") {
+                failed.store(true, Ordering::Relaxed);
+                eprintln!(
+                    "error[{}]: {}",
+                    response["code"].as_str().unwrap_or("unknown"),
+                    error.as_str().unwrap_or_default()
+                );
+                return true;
+            }
+            false
+        }
+    };
+
+    let open = json!({
+        "method": "open_repository",
+        "params": { "path": args.repo.to_string_lossy() },
+    });
+    let open: Request = serde_json::from_value(open).unwrap();
+    let on_open = report.clone();
+    explorer.handle(open, move |response| {
+        on_open(serde_json::to_value(response).unwrap());
+    });
+
+    if failed.load(Ordering::Relaxed) {
+        std::process::exit(1);
+    }
+
+    let kind = Kind::from(&args.cmd);
+    let request: Request = serde_json::from_value(args.cmd.request()).unwrap();
+
+    explorer.handle(request, move |response| {
+        let response = serde_json::to_value(response).unwrap();
+        if report(response.clone()) {
+            return;
+        }
+
+        let data = &response["data"];
+
+        // the closing message of a stream carries no payload
+        if response["end"] == true && matches!(kind, Kind::Commits) {
+            return;
+        }
+
+        let mut out = io::stdout().lock();
+        let result = match output {
+            Output::Text => print_text(&mut out, kind, data),
+            Output::Json => writeln!(out, "{}", data),
+            Output::Pretty => writeln!(out, "{}", serde_json::to_string_pretty(data).unwrap()),
+        };
+
+        // stop quietly when piped into e.g. `head`
+        if let Err(err) = result {
+            if err.kind() == io::ErrorKind::BrokenPipe {
+                std::process::exit(0);
+            }
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    });
+
+    if failed.load(Ordering::Relaxed) {
+        std::process::exit(1);
+    }
+}
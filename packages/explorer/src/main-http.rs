@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufReader};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::{mpsc, Arc, Mutex};
+
+use explorer::http::{self, HttpRequest};
+use explorer::transport::Transport;
+use explorer::websocket::{self, WebSocketTransport};
+use explorer::{
+    CancellationRegistry, Command, Explorer, ExplorerError, Request, Response, METHODS,
+};
+use git2::Repository;
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "explorer-http",
+    about = "Serves the explorer protocol over HTTP and WebSocket on a loopback address"
+)]
+struct Args {
+    /// Repository to serve, as `name=path` or just `path` (named after its directory).
+    /// May be given multiple times.
+    #[structopt(long = "repo", required = true, number_of_values = 1)]
+    repos: Vec<String>,
+
+    /// Address to listen on. Only loopback addresses are accepted.
+    #[structopt(long, default_value = "127.0.0.1:7357")]
+    listen: SocketAddr,
+}
+
+fn parse_repo(spec: &str) -> (String, PathBuf) {
+    if let Some((name, path)) = spec.split_once('=') {
+        return (name.to_string(), PathBuf::from(path));
+    }
+
+    let path = PathBuf::from(spec);
+    let name = path
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| spec.to_string());
+
+    (name, path)
+}
+
+/// Maps an error response onto the closest HTTP status.
+fn status_for(response: &Value) -> u16 {
+    match response["code"].as_str() {
+        None => 200,
+        Some("invalid_params") => 400,
+        Some("rev_not_found") | Some("path_not_found") | Some("unknown_repository") => 404,
+        Some(_) => 500,
+    }
+}
+
+fn is_streaming(method: &str) -> bool {
+    METHODS
+        .iter()
+        .any(|m| m.method == method && m.stream.is_some())
+}
+
+fn new_explorer(path: &PathBuf) -> Result<Explorer, ExplorerError> {
+    let repo = Repository::open(path)
+        .map_err(|e| ExplorerError::from_path_lookup(&path.to_string_lossy(), e))?;
+
+    let mut explorer = Explorer::new();
+    explorer.set_repo(&path.to_string_lossy(), repo);
+    Ok(explorer)
+}
+
+/// Idle explorers kept per repository; more are opened while requests overlap.
+const POOL_SIZE: usize = 4;
+
+/// Explorers for one repository, kept between requests so that the repository, its
+/// `.mailmap` and bot patterns are not loaded again for every request.
+struct ExplorerPool {
+    path: PathBuf,
+    idle: Mutex<Vec<Explorer>>,
+}
+
+impl ExplorerPool {
+    fn new(path: PathBuf) -> Self {
+        ExplorerPool {
+            path,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn take(&self) -> Result<Explorer, ExplorerError> {
+        if let Some(explorer) = self.idle.lock().unwrap().pop() {
+            return Ok(explorer);
+        }
+        new_explorer(&self.path)
+    }
+
+    fn put(&self, explorer: Explorer) {
+        // a client asked this one to shut down; it is not handed out again
+        if explorer.shutdown.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < POOL_SIZE {
+            idle.push(explorer);
+        }
+    }
+}
+
+/// Host names that refer to this machine, as they appear in `Host` and `Origin` headers.
+const LOOPBACK_HOSTS: &[&str] = &["127.0.0.1", "localhost", "[::1]"];
+
+/// Splits `host[:port]` into its parts; bracketed IPv6 hosts keep their brackets.
+fn split_authority(authority: &str) -> (&str, Option<&str>) {
+    match authority.rfind(':') {
+        Some(colon) if !authority[colon..].contains(']') => {
+            (&authority[..colon], Some(&authority[colon + 1..]))
+        }
+        _ => (authority, None),
+    }
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    LOOPBACK_HOSTS.iter().any(|h| h.eq_ignore_ascii_case(host))
+}
+
+/// Whether the request was addressed to this server by a page served from this machine.
+///
+/// Listening on loopback alone does not keep websites out: a page can rebind its own
+/// domain to 127.0.0.1 (DNS rebinding) and post to us, so the `Host` must name the
+/// loopback address and port we listen on, and a browser's `Origin` must be local too.
+fn is_allowed(request: &HttpRequest, port: u16) -> bool {
+    let port = port.to_string();
+    let host_allowed = request
+        .header("host")
+        .map(split_authority)
+        .is_some_and(|(host, p)| is_loopback_host(host) && p == Some(port.as_str()));
+
+    let origin_allowed = match request.header("origin") {
+        None => true,
+        Some(origin) => origin
+            .strip_prefix("http://")
+            .or_else(|| origin.strip_prefix("https://"))
+            .map(|rest| split_authority(rest.split('/').next().unwrap_or_default()).0)
+            .is_some_and(is_loopback_host),
+    };
+
+    host_allowed && origin_allowed
+}
+
+/// Clients work on the repositories given on the command line; opening others would let
+/// any local page read arbitrary repositories on disk.
+fn check_command(command: &Command) -> Result<(), ExplorerError> {
+    match command {
+        Command::OpenRepository(_) | Command::CloseRepository(_) => {
+            Err(ExplorerError::InvalidParams(
+                "repositories are chosen when the server starts".to_string(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn error_body(id: Option<u64>, err: &ExplorerError) -> String {
+    Explorer::serialize_response(Response::error(id, err)).unwrap()
+}
+
+/// Writes the responses to a posted request as the explorer produces them.
+struct PostResponder {
+    stream: TcpStream,
+    streaming: bool,
+    /// Whether a chunked body has been started.
+    chunked: bool,
+    /// Whether the final response has been written.
+    answered: bool,
+}
+
+impl PostResponder {
+    fn write(&mut self, response: &Value) -> io::Result<()> {
+        let is_error = response.get("error").is_some();
+
+        if !self.chunked {
+            if !self.streaming || is_error {
+                self.answered = true;
+                let status = status_for(response);
+                return http::write_json(&mut self.stream, status, &response.to_string());
+            }
+            http::write_chunked_head(&mut self.stream, 200, "application/x-ndjson")?;
+            self.chunked = true;
+        }
+
+        http::write_chunk(&mut self.stream, format!("{}\n", response).as_bytes())?;
+        if is_error || response["end"] == true {
+            self.answered = true;
+            http::write_chunk(&mut self.stream, &[])?;
+        }
+        Ok(())
+    }
+
+    /// Completes the response if the explorer stopped without a final message.
+    fn finish(&mut self) -> io::Result<()> {
+        if self.answered {
+            return Ok(());
+        }
+        if self.chunked {
+            return http::write_chunk(&mut self.stream, &[]);
+        }
+
+        let err = ExplorerError::Internal("request produced no response".to_string());
+        http::write_json(&mut self.stream, 500, &error_body(None, &err))
+    }
+}
+
+/// Handles a single request posted to `/repos/{name}`.
+///
+/// Regular methods answer with their response document. Streaming methods answer with
+/// chunked newline-delimited JSON, one message per line as it is produced, unless they
+/// fail before the first item; that error is answered like a regular response.
+fn handle_post(
+    stream: &mut TcpStream,
+    pool: &ExplorerPool,
+    request: &HttpRequest,
+) -> io::Result<()> {
+    let body = String::from_utf8_lossy(&request.body).to_string();
+
+    let parsed = match Explorer::deserialize_request(body.clone()) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            let payload = error_body(Request::peek_id(&body), &ExplorerError::from(err));
+            return http::write_json(stream, 400, &payload);
+        }
+    };
+
+    if let Err(err) = check_command(&parsed.command) {
+        return http::write_json(stream, 400, &error_body(parsed.id, &err));
+    }
+
+    let method = serde_json::to_value(&parsed.command)
+        .ok()
+        .and_then(|v| v["method"].as_str().map(String::from))
+        .unwrap_or_default();
+
+    let mut explorer = match pool.take() {
+        Ok(explorer) => explorer,
+        Err(err) => return http::write_json(stream, 500, &error_body(parsed.id, &err)),
+    };
+
+    let responder = Arc::new(Mutex::new(PostResponder {
+        stream: stream.try_clone()?,
+        streaming: is_streaming(&method),
+        chunked: false,
+        answered: false,
+    }));
+
+    let writer = responder.clone();
+    let cancellations = explorer.cancellation_registry();
+    let id = parsed.id;
+    explorer.handle(parsed, move |response| {
+        let response = serde_json::to_value(response).unwrap();
+        if writer.lock().unwrap().write(&response).is_err() {
+            // the client is gone, so there is no point in producing the rest
+            if let Some(id) = id {
+                cancellations.cancel(id);
+            }
+        }
+    });
+    pool.put(explorer);
+
+    let mut responder = responder.lock().unwrap();
+    responder.finish()
+}
+
+fn write_ws(transport: &WebSocketTransport, response: Response) {
+    let result = Explorer::serialize_response(response)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .and_then(|payload| transport.write_message(&payload));
+
+    if let Err(err) = result {
+        eprintln!("Failed to write response: {}", err);
+    }
+}
+
+/// Serves one WebSocket connection with an explorer taken from the pool.
+///
+/// Requests run one at a time on a worker thread while this thread keeps reading,
+/// so `cancel` can stop a stream that is still in flight.
+fn serve_websocket(stream: TcpStream, pool: &ExplorerPool) -> io::Result<()> {
+    let transport = Arc::new(WebSocketTransport::new(stream)?);
+
+    let mut explorer = match pool.take() {
+        Ok(explorer) => explorer,
+        Err(err) => {
+            write_ws(&transport, Response::error(None, &err));
+            return Ok(());
+        }
+    };
+
+    let cancellations = explorer.cancellation_registry();
+
+    // the worker returns the explorer to the pool once the connection is gone and
+    // `tx` dropped, and the scope waits for that
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel::<Request>();
+
+        let writer = transport.clone();
+        scope.spawn(move || {
+            for request in rx {
+                let writer = writer.clone();
+                explorer.handle(request, move |response| write_ws(&writer, response));
+
+                if explorer.shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+            pool.put(explorer);
+        });
+
+        serve_websocket_messages(&transport, &cancellations, tx)
+    })
+}
+
+/// Reads the messages of a WebSocket connection and queues its requests on `tx`.
+fn serve_websocket_messages(
+    transport: &WebSocketTransport,
+    cancellations: &CancellationRegistry,
+    tx: mpsc::Sender<Request>,
+) -> io::Result<()> {
+    while let Some(message) = transport.read_message()? {
+        if message.is_empty() {
+            continue;
+        }
+
+        if message == "PING" {
+            transport.write_message("PONG")?;
+            continue;
+        }
+
+        let request = match Explorer::deserialize_request(message.clone()) {
+            Ok(request) => request,
+            Err(err) => {
+                let id = Request::peek_id(&message);
+                write_ws(transport, Response::error(id, &ExplorerError::from(err)));
+                continue;
+            }
+        };
+
+        if let Err(err) = check_command(&request.command) {
+            write_ws(transport, Response::error(request.id, &err));
+            continue;
+        }
+
+        // applied right away; queuing it would wait for the request it should stop
+        if let Command::Cancel(params) = &request.command {
+            let cancelled = cancellations.cancel(params.id);
+            write_ws(transport, Response::data(request.id, cancelled, true));
+            continue;
+        }
+
+        if let Some(id) = request.id {
+            cancellations.register(id);
+        }
+        if tx.send(request).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    repos: &BTreeMap<String, ExplorerPool>,
+    port: u16,
+) -> io::Result<()> {
+    let request = http::read_request(&mut BufReader::new(stream.try_clone()?))?;
+
+    if !is_allowed(&request, port) {
+        return http::write_response(&mut stream, 403, "text/plain", b"forbidden");
+    }
+
+    let segments: Vec<&str> = request
+        .path
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["repos"]) => {
+            let list: Vec<Value> = repos
+                .iter()
+                .map(|(name, pool)| json!({ "name": name, "path": pool.path.to_string_lossy() }))
+                .collect();
+            http::write_json(&mut stream, 200, &Value::from(list).to_string())
+        }
+        (method, ["repos", name, rest @ ..]) => {
+            let pool = match repos.get(*name) {
+                Some(pool) => pool,
+                None => {
+                    let err = ExplorerError::InvalidParams(format!("unknown repository: {}", name));
+                    return http::write_json(&mut stream, 404, &error_body(None, &err));
+                }
+            };
+
+            match (method, rest) {
+                ("POST", []) => handle_post(&mut stream, pool, &request),
+                ("GET", ["ws"]) if request.is_websocket_upgrade() => {
+                    websocket::accept(&mut stream, &request)?;
+                    serve_websocket(stream, pool)
+                }
+                _ => http::write_response(&mut stream, 405, "text/plain", b"method not allowed"),
+            }
+        }
+        _ => http::write_response(&mut stream, 404, "text/plain", b"not found"),
+    }
+}
+
+pub fn main() {
+    let args = Args::from_args();
+
+    // the protocol has no authentication, so it must not be reachable from the network
+    if !args.listen.ip().is_loopback() {
+        eprintln!("Error: refusing to listen on non-loopback address {}", args.listen);
+        std::process::exit(1);
+    }
+
+    let mut repos = BTreeMap::new();
+    for spec in &args.repos {
+        let (name, path) = parse_repo(spec);
+
+        // opened right away, so that a bad path fails here rather than on the first request
+        let pool = ExplorerPool::new(path.clone());
+        match new_explorer(&path) {
+            Ok(explorer) => pool.put(explorer),
+            Err(e) => {
+                eprintln!("Error: cannot open repository {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+
+        if repos.insert(name.clone(), pool).is_some() {
+            eprintln!("Error: repository name {} is used more than once", name);
+            std::process::exit(1);
+        }
+    }
+
+    let listener = match TcpListener::bind(args.listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // the `Host` check needs the actual port if an ephemeral one was requested
+    let local = listener.local_addr().unwrap_or(args.listen);
+
+    eprintln!("Listening on http://{}", local);
+    for name in repos.keys() {
+        eprintln!("  /repos/{}", name);
+    }
+
+    let repos = Arc::new(repos);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Failed to accept connection: {}", err);
+                continue;
+            }
+        };
+
+        let repos = repos.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = handle_connection(stream, &repos, local.port()) {
+                eprintln!("Connection closed with error: {}", err);
+            }
+        });
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use explorer::transport::{serve, FramedTransport, LineTransport, ServeOptions};
+use explorer::{Explorer, OpenRepositoryParams};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "explorer-server", about = "Serves the explorer protocol natively")]
+struct Args {
+    /// Repository opened before the first request is read.
+    #[structopt(long, parse(from_os_str))]
+    repo: Option<PathBuf>,
+
+    /// Listen on a unix domain socket instead of stdin/stdout.
+    #[structopt(long, parse(from_os_str))]
+    socket: Option<PathBuf>,
+
+    /// Message framing: `line` (newline-delimited JSON) or `length` (u32 length prefix).
+    #[structopt(long, default_value = "line")]
+    framing: String,
+}
+
+fn new_explorer(args: &Args) -> Explorer {
+    let mut explorer = Explorer::new();
+
+    if let Some(repo) = &args.repo {
+        explorer.cmd_open_repository(&OpenRepositoryParams {
+            path: repo.to_string_lossy().to_string(),
+            mailmap_rev: None,
+            bot_patterns: None,
+        });
+    }
+
+    explorer
+}
+
+fn serve_stdio(args: &Args) -> std::io::Result<()> {
+    let mut explorer = new_explorer(args);
+    let options = ServeOptions::default();
+
+    match args.framing.as_str() {
+        "length" => serve(
+            &mut explorer,
+            Arc::new(FramedTransport::new(std::io::stdin(), std::io::stdout())),
+            &options,
+        ),
+        _ => serve(&mut explorer, Arc::new(LineTransport::stdio()), &options),
+    }
+}
+
+#[cfg(unix)]
+fn serve_socket(args: Args, path: PathBuf) -> std::io::Result<()> {
+    use explorer::transport::unix;
+
+    let listener = unix::bind(&path)?;
+    let args = Arc::new(args);
+
+    eprintln!("Listening on {}", path.display());
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Failed to accept connection: {}", err);
+                continue;
+            }
+        };
+
+        let args = args.clone();
+
+        // every connection gets its own explorer and repository handle
+        std::thread::spawn(move || {
+            let mut explorer = new_explorer(&args);
+            let options = ServeOptions::default();
+
+            let result = match args.framing.as_str() {
+                "length" => unix::framed_transport(stream)
+                    .and_then(|t| serve(&mut explorer, Arc::new(t), &options)),
+                _ => unix::line_transport(stream)
+                    .and_then(|t| serve(&mut explorer, Arc::new(t), &options)),
+            };
+
+            if let Err(err) = result {
+                eprintln!("Connection closed with error: {}", err);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_args: Args, _path: PathBuf) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "unix domain sockets are not supported on this platform",
+    ))
+}
+
+pub fn main() {
+    let args = Args::from_args();
+
+    if !matches!(args.framing.as_str(), "line" | "length") {
+        eprintln!("Error: unknown framing {}", args.framing);
+        std::process::exit(1);
+    }
+
+    let result = match args.socket.clone() {
+        Some(path) => serve_socket(args, path),
+        None => serve_stdio(&args),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,224 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bindings")]
+use specta::Type;
+
+lazy_static! {
+    static ref CONVENTIONAL_SUBJECT: Regex =
+        Regex::new(r"^(?P<type>[A-Za-z][\w-]*)(?:\((?P<scope>[^()]*)\))?(?P<breaking>!)?: \S")
+            .unwrap();
+    static ref BREAKING_FOOTER: Regex = Regex::new(r"(?m)^BREAKING[ -]CHANGE: ").unwrap();
+    static ref IDENTITY: Regex = Regex::new(r"^(?P<name>.*?)\s*<(?P<email>[^<>]*)>$").unwrap();
+}
+
+/// The `type(scope)!: description` prefix of a Conventional Commits subject.
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConventionalCommit {
+    /// Lowercased, e.g. `feat` or `fix`.
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    pub scope: Option<String>,
+
+    /// Set by a `!` before the colon or a `BREAKING CHANGE:` footer.
+    pub breaking: bool,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Trailer {
+    /// As written, e.g. `Co-authored-by`.
+    pub key: String,
+    pub value: String,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParsedMessage {
+    /// The first paragraph, joined into one line.
+    pub subject: String,
+
+    /// Everything between the subject and the trailers.
+    pub body: String,
+
+    /// `null` if the subject does not follow Conventional Commits.
+    pub conventional: Option<ConventionalCommit>,
+
+    /// Git trailers such as `Signed-off-by`, `Reviewed-by` or `Fixes`, in order.
+    pub trailers: Vec<Trailer>,
+}
+
+pub fn parse_message(message: &str) -> ParsedMessage {
+    let message = message.replace("\r\n", "\n");
+
+    let mut paragraphs = message
+        .split("\n\n")
+        .map(|paragraph| paragraph.trim_matches('\n'))
+        .filter(|paragraph| !paragraph.trim().is_empty());
+
+    let subject = paragraphs
+        .next()
+        .map(|paragraph| {
+            paragraph
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default();
+    let mut rest: Vec<&str> = paragraphs.collect();
+
+    let trailers: Vec<Trailer> = match git2::message_trailers_strs(&message) {
+        Ok(trailers) => trailers
+            .iter()
+            .map(|(key, value)| Trailer {
+                key: key.to_string(),
+                value: value.to_string(),
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    // git only recognises trailers in the last paragraph
+    if !trailers.is_empty() {
+        rest.pop();
+    }
+    let body = rest.join("\n\n");
+
+    let conventional = CONVENTIONAL_SUBJECT
+        .captures(&subject)
+        .map(|captures| ConventionalCommit {
+            kind: captures["type"].to_lowercase(),
+            scope: captures
+                .name("scope")
+                .map(|scope| scope.as_str().trim().to_string()),
+            breaking: captures.name("breaking").is_some() || BREAKING_FOOTER.is_match(&message),
+        });
+
+    ParsedMessage {
+        subject,
+        body,
+        conventional,
+        trailers,
+    }
+}
+
+/// Name and email of everyone credited in a `Co-authored-by` trailer, in order.
+pub fn co_authors(message: &str) -> Vec<(String, String)> {
+    let trailers = match git2::message_trailers_strs(message) {
+        Ok(trailers) => trailers,
+        Err(_) => return Vec::new(),
+    };
+
+    trailers
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("co-authored-by"))
+        .filter_map(|(_, value)| {
+            let captures = IDENTITY.captures(value.trim())?;
+            Some((
+                captures["name"].trim().to_string(),
+                captures["email"].trim().to_string(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{co_authors, parse_message};
+
+    #[test]
+    fn parses_conventional_subjects() {
+        let conventional = parse_message("feat(api): add search").conventional.unwrap();
+        assert_eq!(conventional.kind, "feat");
+        assert_eq!(conventional.scope.as_deref(), Some("api"));
+        assert!(!conventional.breaking);
+
+        let conventional = parse_message("Fix: handle empty repositories")
+            .conventional
+            .unwrap();
+        assert_eq!(conventional.kind, "fix");
+        assert_eq!(conventional.scope, None);
+
+        assert!(parse_message("Update README").conventional.is_none());
+        assert!(parse_message("feat:no space").conventional.is_none());
+        assert!(parse_message("feat(a)(b): nested scope")
+            .conventional
+            .is_none());
+    }
+
+    #[test]
+    fn detects_breaking_changes() {
+        let conventional = parse_message("refactor(core)!: drop the v1 protocol")
+            .conventional
+            .unwrap();
+        assert_eq!(conventional.scope.as_deref(), Some("core"));
+        assert!(conventional.breaking);
+
+        for footer in [
+            "BREAKING CHANGE: ids changed",
+            "BREAKING-CHANGE: ids changed",
+        ] {
+            let message = format!("feat: new ids\n\nDetails.\n\n{}\n", footer);
+            assert!(parse_message(&message).conventional.unwrap().breaking);
+        }
+
+        // only as a footer, not mentioned in passing
+        let message = "feat: new ids\n\nThis is no BREAKING CHANGE: really.\n";
+        assert!(!parse_message(message).conventional.unwrap().breaking);
+    }
+
+    #[test]
+    fn splits_body_from_trailers() {
+        let message =
+            "Add the mailmap\nto author stats\n\nFirst paragraph.\n\nSecond\nparagraph.\n\n\
+                       Signed-off-by: Alice <alice@x.org>\nFixes: #12\n";
+        let parsed = parse_message(message);
+
+        assert_eq!(parsed.subject, "Add the mailmap to author stats");
+        assert_eq!(parsed.body, "First paragraph.\n\nSecond\nparagraph.");
+        let trailers: Vec<_> = parsed
+            .trailers
+            .iter()
+            .map(|trailer| (trailer.key.as_str(), trailer.value.as_str()))
+            .collect();
+        assert_eq!(
+            trailers,
+            [("Signed-off-by", "Alice <alice@x.org>"), ("Fixes", "#12")]
+        );
+        assert!(parsed.conventional.is_none());
+    }
+
+    #[test]
+    fn keeps_last_paragraph_without_trailers() {
+        let parsed =
+            parse_message("Subject\r\n\r\nBody.\r\n\r\nNot: a trailer block\r\nreally\r\n");
+
+        assert_eq!(parsed.subject, "Subject");
+        assert_eq!(parsed.body, "Body.\n\nNot: a trailer block\nreally");
+        assert!(parsed.trailers.is_empty());
+    }
+
+    #[test]
+    fn lists_every_co_author_trailer() {
+        let message = "pair on a\n\n\
+                       Co-authored-by: Eve <eve@x.org>\n\
+                       Co-authored-by: Alice <alice@x.org>\n\
+                       co-authored-by: Eve <eve@x.org>\n\
+                       Co-authored-by: nobody\n\
+                       Signed-off-by: Bob <bob@x.org>\n";
+
+        // duplicates are left to the callers, which dedupe by author id
+        assert_eq!(
+            co_authors(message),
+            [
+                ("Eve".to_string(), "eve@x.org".to_string()),
+                ("Alice".to_string(), "alice@x.org".to_string()),
+                ("Eve".to_string(), "eve@x.org".to_string()),
+            ]
+        );
+        assert!(co_authors("no trailers\n\nCo-authored-by in the body").is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use base64::prelude::*;
+
+use crate::error::{ExplorerError, ExplorerResult};
+
+/// Opaque position in a paginated listing.
+///
+/// A cursor names where the previous page ended, such as its last item, rather than
+/// an offset, so paging stays stable when items are added in front of it (e.g. new
+/// commits).
+/// It is only valid for the ordering it was created with.
+pub struct Cursor;
+
+impl Cursor {
+    pub fn encode(order: &str, after: &str) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(format!("{}:{}", order, after))
+    }
+
+    /// Returns the id of the last item of the previous page.
+    pub fn decode(cursor: &str, order: &str) -> ExplorerResult<String> {
+        let invalid = || ExplorerError::InvalidParams(format!("invalid cursor: {}", cursor));
+
+        let decoded = BASE64_URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+
+        let (cursor_order, after) = decoded.split_once(':').ok_or_else(invalid)?;
+
+        if cursor_order != order {
+            return Err(ExplorerError::InvalidParams(format!(
+                "cursor was created for order `{}`, not `{}`",
+                cursor_order, order
+            )));
+        }
+
+        Ok(after.to_string())
+    }
+}
+
+pub fn validate_limit(limit: Option<u32>) -> ExplorerResult<()> {
+    match limit {
+        Some(0) => Err(ExplorerError::InvalidParams(
+            "limit must be at least 1".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Cuts the page following `cursor` out of `items`, which must already be sorted
+/// by `order`. Returns the page and the cursor of the next one, if there is one.
+pub fn paginate<T>(
+    items: Vec<T>,
+    id: impl Fn(&T) -> &str,
+    order: &str,
+    limit: Option<u32>,
+    cursor: Option<&str>,
+) -> ExplorerResult<(Vec<T>, Option<String>)> {
+    validate_limit(limit)?;
+
+    let start = match cursor {
+        Some(cursor) => {
+            let after = Cursor::decode(cursor, order)?;
+            let position = items
+                .iter()
+                .position(|item| id(item) == after)
+                .ok_or_else(|| {
+                    ExplorerError::InvalidParams(format!(
+                        "cursor does not match any item: {}",
+                        cursor
+                    ))
+                })?;
+            position + 1
+        }
+        None => 0,
+    };
+
+    let mut page: Vec<T> = items.into_iter().skip(start).collect();
+
+    let next_cursor = match limit {
+        Some(limit) if page.len() > limit as usize => {
+            page.truncate(limit as usize);
+            page.last().map(|last| Cursor::encode(order, id(last)))
+        }
+        _ => None,
+    };
+
+    Ok((page, next_cursor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip_for_their_order_only() {
+        let cursor = Cursor::encode("name", "v1-581bde9c4a1b5229");
+        assert_eq!(
+            Cursor::decode(&cursor, "name").unwrap(),
+            "v1-581bde9c4a1b5229"
+        );
+
+        assert!(matches!(
+            Cursor::decode(&cursor, "num_commits"),
+            Err(ExplorerError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            Cursor::decode("not a cursor!", "name"),
+            Err(ExplorerError::InvalidParams(_))
+        ));
+        // valid base64, but without an order
+        let no_order = BASE64_URL_SAFE_NO_PAD.encode("v1-581bde9c4a1b5229");
+        assert!(matches!(
+            Cursor::decode(&no_order, "name"),
+            Err(ExplorerError::InvalidParams(_))
+        ));
+    }
+
+    #[test]
+    fn pages_follow_each_other() {
+        let items = vec!["a", "b", "c", "d", "e"];
+        let page = |cursor: Option<&str>| paginate(items.clone(), |i| i, "name", Some(2), cursor);
+
+        let (first, cursor) = page(None).unwrap();
+        assert_eq!(first, ["a", "b"]);
+        let (second, cursor) = page(cursor.as_deref()).unwrap();
+        assert_eq!(second, ["c", "d"]);
+        let (last, cursor) = page(cursor.as_deref()).unwrap();
+        assert_eq!(last, ["e"]);
+        assert_eq!(cursor, None);
+
+        // a page that ends exactly with the items has no successor either
+        let (all, cursor) = paginate(items.clone(), |i| i, "name", Some(5), None).unwrap();
+        assert_eq!(all.len(), 5);
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn rejects_zero_limits_and_unknown_cursors() {
+        let items = vec!["a", "b"];
+
+        assert!(paginate(items.clone(), |i| i, "name", Some(0), None).is_err());
+
+        let gone = Cursor::encode("name", "z");
+        assert!(matches!(
+            paginate(items, |i| i, "name", None, Some(&gone)),
+            Err(ExplorerError::InvalidParams(_))
+        ));
+    }
+}
@@ -0,0 +1,172 @@
+/// Describes one method of the request/response protocol in terms of its TypeScript types.
+///
+/// This table is the source for `get_capabilities` and for the method maps emitted by the
+/// bindings binary, so adding a `Command` variant means adding an entry here; the tests
+/// below check that the two agree.
+pub struct MethodInfo {
+    pub method: &'static str,
+    pub params: &'static str,
+    /// Payload of the final (`end: true`) response.
+    pub result: &'static str,
+    /// Payload of intermediate responses, for streaming methods.
+    pub stream: Option<&'static str>,
+}
+
+const fn method(method: &'static str, params: &'static str, result: &'static str) -> MethodInfo {
+    MethodInfo {
+        method,
+        params,
+        result,
+        stream: None,
+    }
+}
+
+const fn stream(
+    method: &'static str,
+    params: &'static str,
+    result: &'static str,
+    item: &'static str,
+) -> MethodInfo {
+    MethodInfo {
+        method,
+        params,
+        result,
+        stream: Some(item),
+    }
+}
+
+pub const METHODS: &[MethodInfo] = &[
+    method("get_commits_for_time_range", "GetCommitsForTimeRangeParams", "CommitRange"),
+    method("open_repository", "OpenRepositoryParams", "OpenRepositoryResult"),
+    stream("stream_authors", "StreamAuthorsParams", "null", "Author"),
+    method("get_authors", "GetAuthorsParams", "GetAuthorsResult"),
+    method("get_file_tree", "GetFileTreeParams", "FileTreeNode[]"),
+    stream("stream_file_tree", "GetFileTreeParams", "string", "FileTreeNode"),
+    method("get_branches", "NoParams", "string[]"),
+    method("get_git_graph", "NoParams", "CommitTree"),
+    method("get_blame", "BlameParams", "Blame"),
+    method("get_file_content", "GetFileContentParams", "GetFileContentResult"),
+    method("get_commits_for_branch", "GetCommitsForBranchParams", "CommitsForBranch"),
+    stream("stream_commits", "StreamCommitsParams", "StreamCommitsResult", "Commit"),
+    method("shutdown", "NoParams", "void"),
+    method("get_initial_data", "NoParams", "InitialDataResult"),
+    method("is_valid_rev", "IsValidRevParams", "boolean"),
+    method("get_commit", "GetCommitParams", "Commit"),
+    method("cancel", "CancelParams", "boolean"),
+    method("get_capabilities", "NoParams", "Capabilities"),
+    method("close_repository", "CloseRepositoryParams", "boolean"),
+    method("list_repositories", "NoParams", "RepositoryInfo[]"),
+    method("get_diff", "GetDiffParams", "DiffResult"),
+    method("map_legacy_author_ids", "MapLegacyAuthorIdsParams", "LegacyAuthorIds"),
+];
+
+/// Renders the per-method type maps that complement the specta-generated types.
+#[cfg(feature = "bindings")]
+pub fn typescript_method_maps() -> String {
+    let mut params = String::new();
+    let mut results = String::new();
+    let mut items = String::new();
+
+    for info in METHODS {
+        params.push_str(&format!("{}: {}; ", info.method, info.params));
+        results.push_str(&format!("{}: {}; ", info.method, info.result));
+        if let Some(item) = info.stream {
+            items.push_str(&format!("{}: {}; ", info.method, item));
+        }
+    }
+
+    format!(
+        "export type MethodParamsMap = {{ {params}}}\n\n\
+         export type MethodResultMap = {{ {results}}}\n\n\
+         export type MethodStreamItemMap = {{ {items}}}\n\n\
+         export type Method = keyof MethodParamsMap\n\n\
+         export type StreamingMethod = keyof MethodStreamItemMap\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::METHODS;
+    use crate::explorer::Command;
+
+    /// Method names `Command` accepts, as listed by serde when it rejects an unknown one.
+    fn command_methods() -> Vec<String> {
+        let err = serde_json::from_str::<Command>(r#"{"method":"","params":null}"#)
+            .unwrap_err()
+            .to_string();
+        let (_, expected) = err
+            .split_once("expected one of ")
+            .expect("serde lists the known variants");
+
+        expected
+            .split('`')
+            .skip(1)
+            .step_by(2)
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn every_command_is_described_once() {
+        let mut described = HashSet::new();
+        for info in METHODS {
+            assert!(
+                described.insert(info.method),
+                "{} is described twice",
+                info.method
+            );
+        }
+
+        let commands = command_methods();
+        for command in &commands {
+            assert!(
+                described.contains(command.as_str()),
+                "{} is not in METHODS",
+                command
+            );
+        }
+        for method in described {
+            assert!(
+                commands.iter().any(|c| c == method),
+                "{} is not a Command",
+                method
+            );
+        }
+    }
+
+    #[cfg(feature = "bindings")]
+    #[test]
+    fn method_types_are_exported() {
+        use specta::ts::{BigIntExportBehavior, ExportConfig};
+
+        const BUILTIN: &[&str] = &["string", "boolean", "number", "null", "void"];
+
+        let path =
+            std::env::temp_dir().join(format!("explorer-protocol-{}.ts", std::process::id()));
+        specta::export::ts_with_cfg(
+            path.to_str().unwrap(),
+            &ExportConfig::default().bigint(BigIntExportBehavior::Number),
+        )
+        .unwrap();
+        let exported = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        for info in METHODS {
+            let types = [info.params, info.result].into_iter().chain(info.stream);
+            for name in types.map(|ty| ty.trim_end_matches("[]")) {
+                if BUILTIN.contains(&name) {
+                    continue;
+                }
+                assert!(
+                    exported.contains(&format!("export type {} =", name))
+                        || exported.contains(&format!("export type {}<", name)),
+                    "{} of {} is not exported",
+                    name,
+                    info.method
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bindings")]
+use specta::Type;
+
+use crate::error::{ExplorerError, ExplorerResult};
+use crate::explorer::Explorer;
+use crate::bots::BotDetector;
+use crate::mailmap::Mailmap;
+
+/// Identifies a repository opened by `open_repository`. Handles are never reused
+/// within the lifetime of an explorer.
+pub type RepoHandle = u32;
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloseRepositoryParams {
+    pub handle: RepoHandle,
+}
+
+#[cfg_attr(feature = "bindings", derive(Type))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepositoryInfo {
+    pub handle: RepoHandle,
+    pub path: String,
+
+    /// Whether requests without a `repo` handle are served by this repository.
+    #[serde(rename = "isDefault")]
+    pub is_default: bool,
+}
+
+struct OpenedRepository {
+    path: String,
+    repo: Repository,
+    mailmap: Mailmap,
+    bots: BotDetector,
+}
+
+/// The repositories opened by an explorer.
+///
+/// The most recently opened repository becomes the default, so clients that never
+/// pass a `repo` handle keep the old behaviour of `open_repository` replacing the
+/// current repository.
+#[derive(Default)]
+pub struct Repositories {
+    opened: BTreeMap<RepoHandle, OpenedRepository>,
+    next_handle: RepoHandle,
+    default: Option<RepoHandle>,
+}
+
+impl Repositories {
+    pub fn insert(
+        &mut self,
+        path: String,
+        repo: Repository,
+        mailmap: Mailmap,
+        bots: BotDetector,
+    ) -> RepoHandle {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+
+        self.opened.insert(
+            handle,
+            OpenedRepository {
+                path,
+                repo,
+                mailmap,
+                bots,
+            },
+        );
+        self.default = Some(handle);
+
+        handle
+    }
+
+    pub fn set_next_handle(&mut self, handle: RepoHandle) {
+        self.next_handle = handle;
+    }
+
+    pub fn remove(&mut self, handle: RepoHandle) -> ExplorerResult<()> {
+        self.opened
+            .remove(&handle)
+            .ok_or(ExplorerError::UnknownRepository(handle))?;
+
+        if self.default == Some(handle) {
+            self.default = None;
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, handle: Option<RepoHandle>) -> ExplorerResult<RepoHandle> {
+        match handle {
+            Some(handle) if self.opened.contains_key(&handle) => Ok(handle),
+            Some(handle) => Err(ExplorerError::UnknownRepository(handle)),
+            None => self.default.ok_or(ExplorerError::NoRepositoryOpen),
+        }
+    }
+
+    pub fn get(&self, handle: Option<RepoHandle>) -> ExplorerResult<&Repository> {
+        let handle = self.resolve(handle)?;
+        Ok(&self.opened[&handle].repo)
+    }
+
+    pub fn get_mut(&mut self, handle: Option<RepoHandle>) -> ExplorerResult<&mut Repository> {
+        let handle = self.resolve(handle)?;
+        Ok(&mut self.opened.get_mut(&handle).unwrap().repo)
+    }
+
+    pub fn get_mailmap(&self, handle: Option<RepoHandle>) -> ExplorerResult<&Mailmap> {
+        let handle = self.resolve(handle)?;
+        Ok(&self.opened[&handle].mailmap)
+    }
+
+    pub fn get_bots(&self, handle: Option<RepoHandle>) -> ExplorerResult<&BotDetector> {
+        let handle = self.resolve(handle)?;
+        Ok(&self.opened[&handle].bots)
+    }
+
+    pub fn list(&self) -> Vec<RepositoryInfo> {
+        self.opened
+            .iter()
+            .map(|(handle, opened)| RepositoryInfo {
+                handle: *handle,
+                path: opened.path.clone(),
+                is_default: self.default == Some(*handle),
+            })
+            .collect()
+    }
+}
+
+impl Explorer {
+    /// Makes the next repository opened by `open_repository` use `handle`. Hosts that
+    /// spread requests over several explorers assign handles themselves, so that a
+    /// repository has the same handle on all of them.
+    pub fn set_next_repo_handle(&mut self, handle: RepoHandle) {
+        self.repositories.set_next_handle(handle);
+    }
+
+    pub fn cmd_close_repository(&mut self, params: &CloseRepositoryParams) {
+        match self.repositories.remove(params.handle) {
+            Ok(()) => self.send(true, true),
+            Err(e) => self.send_error(e),
+        }
+    }
+
+    pub fn cmd_list_repositories(&self) {
+        self.send(self.repositories.list(), true);
+    }
+}
@@ -0,0 +1,190 @@
+//! Throwaway repositories for unit tests, built commit by commit without a
+//! working tree.
+
+use std::cell::Cell;
+use std::path::PathBuf;
+
+use git2::build::TreeUpdateBuilder;
+use git2::{FileMode, ObjectType, Oid, Repository};
+
+use crate::explorer::Explorer;
+
+/// Author time of the first commit; each further commit is a minute later.
+const START_TIME: i64 = 1_700_000_000;
+
+/// A repository in the temporary directory, removed again when dropped.
+pub(crate) struct TestRepo {
+    pub(crate) path: PathBuf,
+    pub(crate) repo: Repository,
+    commits: Cell<i64>,
+}
+
+impl TestRepo {
+    /// `name` keeps the repositories of tests that run in parallel apart.
+    pub(crate) fn new(name: &str) -> TestRepo {
+        let path =
+            std::env::temp_dir().join(format!("explorer-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let repo = Repository::init(&path).unwrap();
+        // the same branch whatever `init.defaultBranch` is set to
+        repo.set_head("refs/heads/main").unwrap();
+
+        TestRepo {
+            path,
+            repo,
+            commits: Cell::new(0),
+        }
+    }
+
+    /// An explorer with this repository opened as its default one.
+    pub(crate) fn explorer(&self) -> Explorer {
+        let mut explorer = Explorer::new();
+        let repo = Repository::open(&self.path).unwrap();
+        explorer.set_repo(&self.path.to_string_lossy(), repo);
+        explorer
+    }
+
+    /// Starts a commit on top of `HEAD`, by Alice unless told otherwise.
+    pub(crate) fn commit(&self, message: &str) -> CommitBuilder<'_> {
+        let count = self.commits.get();
+        self.commits.set(count + 1);
+
+        CommitBuilder {
+            repo: self,
+            message: message.to_string(),
+            author: (b"Alice".to_vec(), "alice@x.org".to_string()),
+            time: START_TIME + count * 60,
+            commit_time: None,
+            parents: None,
+            update_ref: "HEAD".to_string(),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+pub(crate) struct CommitBuilder<'r> {
+    repo: &'r TestRepo,
+    message: String,
+    author: (Vec<u8>, String),
+    time: i64,
+    commit_time: Option<i64>,
+    parents: Option<Vec<Oid>>,
+    update_ref: String,
+    files: Vec<(String, Option<Vec<u8>>)>,
+}
+
+impl CommitBuilder<'_> {
+    pub(crate) fn author(self, name: &str, email: &str) -> Self {
+        self.author_bytes(name.as_bytes(), email)
+    }
+
+    /// An author name that need not be UTF-8, such as one written in Latin-1.
+    pub(crate) fn author_bytes(mut self, name: &[u8], email: &str) -> Self {
+        self.author = (name.to_vec(), email.to_string());
+        self
+    }
+
+    /// The author time, which is also the commit time unless `committed_at` is set.
+    pub(crate) fn time(mut self, time: i64) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub(crate) fn committed_at(mut self, time: i64) -> Self {
+        self.commit_time = Some(time);
+        self
+    }
+
+    /// Replaces the tip of the updated ref as the parents; the tree starts from the
+    /// first of them.
+    pub(crate) fn parents(mut self, parents: &[Oid]) -> Self {
+        self.parents = Some(parents.to_vec());
+        self
+    }
+
+    /// Updates `reference`, such as `refs/heads/side`, instead of `HEAD`.
+    pub(crate) fn on(mut self, reference: &str) -> Self {
+        self.update_ref = reference.to_string();
+        self
+    }
+
+    pub(crate) fn file(mut self, path: &str, content: impl AsRef<[u8]>) -> Self {
+        self.files
+            .push((path.to_string(), Some(content.as_ref().to_vec())));
+        self
+    }
+
+    pub(crate) fn remove(mut self, path: &str) -> Self {
+        self.files.push((path.to_string(), None));
+        self
+    }
+
+    pub(crate) fn create(self) -> Oid {
+        let repo = &self.repo.repo;
+
+        let parents = match self.parents {
+            Some(parents) => parents,
+            None => repo.refname_to_id(&self.update_ref).into_iter().collect(),
+        };
+        let parents: Vec<_> = parents
+            .iter()
+            .map(|oid| repo.find_commit(*oid).unwrap())
+            .collect();
+
+        let base = match parents.first() {
+            Some(parent) => parent.tree().unwrap(),
+            None => {
+                let empty = repo.treebuilder(None).unwrap().write().unwrap();
+                repo.find_tree(empty).unwrap()
+            }
+        };
+
+        let mut update = TreeUpdateBuilder::new();
+        for (path, content) in &self.files {
+            match content {
+                Some(content) => {
+                    let blob = repo.blob(content).unwrap();
+                    update.upsert(path, blob, FileMode::Blob);
+                }
+                None => {
+                    update.remove(path);
+                }
+            }
+        }
+        let tree = update.create_updated(repo, &base).unwrap();
+
+        // written by hand, as `Signature` only takes UTF-8 names
+        let mut object = format!("tree {}\n", tree).into_bytes();
+        for parent in &parents {
+            object.extend(format!("parent {}\n", parent.id()).bytes());
+        }
+        let (name, email) = &self.author;
+        let commit_time = self.commit_time.unwrap_or(self.time);
+        for (role, time) in [("author", self.time), ("committer", commit_time)] {
+            object.extend(format!("{} ", role).bytes());
+            object.extend(name);
+            object.extend(format!(" <{}> {} +0000\n", email, time).bytes());
+        }
+        object.extend(format!("\n{}\n", self.message).bytes());
+        let oid = repo
+            .odb()
+            .unwrap()
+            .write(ObjectType::Commit, &object)
+            .unwrap();
+
+        // `HEAD` moves the branch it points to
+        let target = repo
+            .find_reference(&self.update_ref)
+            .ok()
+            .and_then(|reference| reference.symbolic_target().map(str::to_string))
+            .unwrap_or_else(|| self.update_ref.clone());
+        repo.reference(&target, oid, true, &self.message).unwrap();
+        oid
+    }
+}
@@ -0,0 +1,462 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::cancellation::{CancellationPoll, CancellationRegistry};
+use crate::error::ExplorerError;
+use crate::explorer::{Command, Explorer, Request, Response};
+
+/// A bidirectional message channel an `Explorer` can be served over.
+///
+/// Messages are complete JSON documents; framing is up to the implementation.
+/// Both methods take `&self` so that responses can be written from the explorer
+/// callback while the serving loop owns the read side.
+pub trait Transport: Send + Sync {
+    /// Reads the next message, or `None` once the peer has closed the stream.
+    fn read_message(&self) -> io::Result<Option<String>>;
+
+    /// Reads a message only if one has already arrived, returning `None` instead of
+    /// waiting for one. `serve` uses it where it cannot read on a thread of its own;
+    /// transports that can only block never find a message.
+    fn try_read_message(&self) -> io::Result<Option<String>> {
+        Ok(None)
+    }
+
+    fn write_message(&self, message: &str) -> io::Result<()>;
+}
+
+/// Newline-delimited messages, as spoken by the WASI build over stdin/stdout.
+pub struct LineTransport<R: Read, W: Write> {
+    reader: Mutex<BufReader<R>>,
+    writer: Mutex<W>,
+}
+
+impl<R: Read, W: Write> LineTransport<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        LineTransport {
+            reader: Mutex::new(BufReader::new(reader)),
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl LineTransport<io::Stdin, io::Stdout> {
+    pub fn stdio() -> Self {
+        LineTransport::new(io::stdin(), io::stdout())
+    }
+}
+
+impl<R: Read + Send, W: Write + Send> Transport for LineTransport<R, W> {
+    fn read_message(&self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        let read = self.reader.lock().unwrap().read_line(&mut line)?;
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+
+    fn write_message(&self, message: &str) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(message.as_bytes())?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
+}
+
+/// Messages prefixed with their length as a big-endian `u32`, for payloads that
+/// may contain raw newlines or for peers that prefer not to scan for delimiters.
+pub struct FramedTransport<R: Read, W: Write> {
+    reader: Mutex<R>,
+    writer: Mutex<W>,
+}
+
+impl<R: Read, W: Write> FramedTransport<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        FramedTransport {
+            reader: Mutex::new(reader),
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<R: Read + Send, W: Write + Send> Transport for FramedTransport<R, W> {
+    fn read_message(&self) -> io::Result<Option<String>> {
+        let mut reader = self.reader.lock().unwrap();
+
+        let mut header = [0u8; 4];
+        match reader.read_exact(&mut header) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let mut payload = vec![0u8; u32::from_be_bytes(header) as usize];
+        reader.read_exact(&mut payload)?;
+
+        String::from_utf8(payload)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn write_message(&self, message: &str) -> io::Result<()> {
+        let length = u32::try_from(message.len())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(&length.to_be_bytes())?;
+        writer.write_all(message.as_bytes())?;
+        writer.flush()
+    }
+}
+
+#[cfg(unix)]
+pub mod unix {
+    use std::io;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+
+    use super::{FramedTransport, LineTransport};
+
+    pub fn line_transport(stream: UnixStream) -> io::Result<LineTransport<UnixStream, UnixStream>> {
+        Ok(LineTransport::new(stream.try_clone()?, stream))
+    }
+
+    pub fn framed_transport(
+        stream: UnixStream,
+    ) -> io::Result<FramedTransport<UnixStream, UnixStream>> {
+        Ok(FramedTransport::new(stream.try_clone()?, stream))
+    }
+
+    /// Binds a listener at `path`, replacing a stale socket file left by a previous run.
+    /// Anything else at `path` is left alone and fails with `AddrInUse`.
+    pub fn bind(path: &Path) -> io::Result<UnixListener> {
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} exists and is not a socket", path.display()),
+                ))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        UnixListener::bind(path)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    /// Stop serving when the peer closes the stream or reading fails. The WASI host
+    /// reports an empty stdin between writes, so it keeps polling instead.
+    pub exit_on_close: bool,
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        ServeOptions {
+            exit_on_close: true,
+        }
+    }
+}
+
+/// A message read by `serve` for the explorer to handle.
+enum Incoming {
+    Request(Request),
+    Invalid(String, serde_json::Error),
+}
+
+/// The read side of `serve`. It answers `PING` and `cancel` itself as soon as they
+/// are read, so a `cancel` reaches the request the explorer is busy with.
+struct Reader<T: Transport> {
+    transport: Arc<T>,
+    cancellations: CancellationRegistry,
+}
+
+impl<T: Transport> Reader<T> {
+    /// Waits for the next message for the explorer, or `None` once the peer has
+    /// closed the stream.
+    fn read(&self) -> io::Result<Option<Incoming>> {
+        while let Some(message) = self.transport.read_message()? {
+            if let Some(incoming) = self.accept(message)? {
+                return Ok(Some(incoming));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Queues the messages that have already arrived, without waiting for more.
+    fn poll(&self, queue: &Sender<io::Result<Option<Incoming>>>) {
+        loop {
+            let incoming = match self.transport.try_read_message() {
+                Ok(Some(message)) => self.accept(message),
+                Ok(None) => break,
+                Err(err) => Err(err),
+            };
+
+            match incoming {
+                Ok(Some(incoming)) => {
+                    let _ = queue.send(Ok(Some(incoming)));
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    eprintln!("Failed to read message: {}", err);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn accept(&self, message: String) -> io::Result<Option<Incoming>> {
+        if message.is_empty() {
+            return Ok(None);
+        }
+
+        if message == "PING" {
+            self.transport.write_message("PONG")?;
+            return Ok(None);
+        }
+
+        let request = match Explorer::deserialize_request(message.clone()) {
+            Ok(request) => request,
+            Err(err) => return Ok(Some(Incoming::Invalid(message, err))),
+        };
+
+        // applied right away; queuing it would wait for the request it should stop
+        if let Command::Cancel(params) = &request.command {
+            let cancelled = self.cancellations.cancel(params.id);
+            write_response(
+                self.transport.as_ref(),
+                Response::data(request.id, cancelled, true),
+            );
+            return Ok(None);
+        }
+
+        if let Some(id) = request.id {
+            self.cancellations.register(id);
+        }
+
+        Ok(Some(Incoming::Request(request)))
+    }
+}
+
+/// Reads for `serve` on a thread of its own until the stream closes or `serve`
+/// has returned and dropped the other end of `queue`.
+fn read_ahead<T: Transport>(
+    reader: &Reader<T>,
+    queue: &Sender<io::Result<Option<Incoming>>>,
+    exit_on_close: bool,
+) {
+    loop {
+        let next = reader.read();
+        let closed = !matches!(next, Ok(Some(_)));
+
+        if queue.send(next).is_err() || (closed && exit_on_close) {
+            break;
+        }
+    }
+}
+
+fn write_response(transport: &impl Transport, response: Response) {
+    let result = Explorer::serialize_response(response)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .and_then(|payload| transport.write_message(&payload));
+
+    if let Err(err) = result {
+        eprintln!("Failed to write response: {}", err);
+    }
+}
+
+/// Reads requests from `transport` and writes every response back to it until the
+/// peer disconnects or a `shutdown` request is received.
+///
+/// Messages are read on a separate thread while a request runs, so that `cancel`
+/// can stop it. Where threads are unavailable, as on wasm32-wasi, the running
+/// request polls the transport for messages instead.
+pub fn serve<T: Transport + 'static>(
+    explorer: &mut Explorer,
+    transport: Arc<T>,
+    options: &ServeOptions,
+) -> io::Result<()> {
+    let reader = Arc::new(Reader {
+        transport: transport.clone(),
+        cancellations: explorer.cancellation_registry(),
+    });
+    let (sender, queue) = mpsc::channel();
+
+    let spawned = thread::Builder::new().spawn({
+        let reader = reader.clone();
+        let sender = sender.clone();
+        let exit_on_close = options.exit_on_close;
+        move || read_ahead(&reader, &sender, exit_on_close)
+    });
+
+    // without a reading thread the queue only holds what polling found, and the
+    // loop reads by itself once it is empty
+    let polling = match spawned {
+        Ok(_) => {
+            // the thread holds the only sender, so the queue closes when it stops
+            drop(sender);
+            false
+        }
+        Err(_) => {
+            let reader = reader.clone();
+            let sender = Mutex::new(sender);
+            explorer.cancellation_poll = Some(CancellationPoll::new(move || {
+                reader.poll(&sender.lock().unwrap())
+            }));
+            true
+        }
+    };
+
+    let result = loop {
+        if explorer.shutdown.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+
+        let next = if polling {
+            queue.try_recv().unwrap_or_else(|_| reader.read())
+        } else {
+            match queue.recv() {
+                Ok(next) => next,
+                Err(_) => break Ok(()),
+            }
+        };
+
+        let incoming = match next {
+            Ok(Some(incoming)) => incoming,
+            Ok(None) if options.exit_on_close => break Ok(()),
+            Ok(None) => continue,
+            Err(err) if options.exit_on_close => break Err(err),
+            Err(err) => {
+                eprintln!("Failed to read message: {}", err);
+                continue;
+            }
+        };
+
+        match incoming {
+            Incoming::Request(request) => {
+                let writer = transport.clone();
+                explorer.handle(request, move |response| {
+                    write_response(writer.as_ref(), response)
+                });
+            }
+            Incoming::Invalid(message, err) => {
+                eprintln!("Failed to deserialize request {}: {}", message, err);
+                write_response(
+                    transport.as_ref(),
+                    Response::error(Request::peek_id(&message), &ExplorerError::from(err)),
+                );
+            }
+        }
+    };
+
+    explorer.cancellation_poll = None;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{Receiver, SyncSender};
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    /// Hands the messages of a test to `serve`. The responses of request 1 go through
+    /// a rendezvous channel, so the explorer cannot get ahead of what the test reads.
+    struct ChannelTransport {
+        incoming: Mutex<Receiver<String>>,
+        stream: SyncSender<Value>,
+        others: Sender<Value>,
+    }
+
+    impl Transport for ChannelTransport {
+        fn read_message(&self) -> io::Result<Option<String>> {
+            Ok(self.incoming.lock().unwrap().recv().ok())
+        }
+
+        fn write_message(&self, message: &str) -> io::Result<()> {
+            let response: Value = serde_json::from_str(message)?;
+            let sent = if response["id"] == 1 {
+                self.stream.send(response).is_ok()
+            } else {
+                self.others.send(response).is_ok()
+            };
+
+            match sent {
+                true => Ok(()),
+                false => Err(io::ErrorKind::BrokenPipe.into()),
+            }
+        }
+    }
+
+    #[test]
+    fn cancel_stops_a_running_stream() {
+        let repo = TestRepo::new("cancel-stream");
+        for i in 0..20 {
+            repo.commit(&format!("commit {}", i))
+                .file("a.txt", i.to_string())
+                .create();
+        }
+        let mut explorer = repo.explorer();
+
+        let (requests, incoming) = mpsc::channel();
+        let (stream, streamed) = mpsc::sync_channel(0);
+        let (others, answers) = mpsc::channel();
+        let transport = Arc::new(ChannelTransport {
+            incoming: Mutex::new(incoming),
+            stream,
+            others,
+        });
+        let server =
+            thread::spawn(move || serve(&mut explorer, transport, &ServeOptions::default()));
+
+        let send = |message: &str| requests.send(message.to_string()).unwrap();
+        send(r#"{"id":1,"method":"stream_commits","params":{}}"#);
+        let first = streamed.recv().unwrap();
+        assert!(first["data"]["oid"].is_string());
+
+        // the explorer waits to hand over the second commit while the cancel lands
+        send(r#"{"id":2,"method":"cancel","params":{"id":1}}"#);
+        assert_eq!(answers.recv().unwrap()["data"], true);
+
+        let mut rest = Vec::new();
+        while rest.last().is_none_or(|r: &Value| r["end"] != true) {
+            rest.push(streamed.recv().unwrap());
+        }
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[1]["cancelled"], true);
+
+        drop(requests);
+        server.join().unwrap().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bind_replaces_only_sockets() {
+        let dir = std::env::temp_dir().join(format!("explorer-bind-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // a socket left behind by a previous run
+        let socket = dir.join("stale.sock");
+        drop(unix::bind(&socket).unwrap());
+        assert!(unix::bind(&socket).is_ok());
+
+        let file = dir.join("notes.txt");
+        std::fs::write(&file, "keep me").unwrap();
+        let err = unix::bind(&file).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
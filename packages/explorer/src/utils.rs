@@ -18,7 +18,36 @@ pub fn print_json<T: Serialize>(data: &T) {
     let _ = lock.flush();
 }
 
-pub fn get_author_id(author_name: &String, author_email: &String) -> String {
+/// Version of the scheme behind `get_author_id`, also the `v<N>-` prefix of every id.
+/// Bumped whenever ids of the same identity would change.
+pub const AUTHOR_ID_VERSION: u32 = 1;
+
+/// Stable id of an author: a truncated SHA-1 of the name with collapsed whitespace
+/// and the lowercased email, so ids can be persisted by clients.
+pub fn get_author_id(author_name: &str, author_email: &str) -> String {
+    let name = author_name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let email = author_email.trim().to_lowercase();
+
+    let mut sha = sha1_smol::Sha1::new();
+    sha.update(name.as_bytes());
+    sha.update(&[0]);
+    sha.update(email.as_bytes());
+
+    format!("v{}-{}", AUTHOR_ID_VERSION, &sha.digest().to_string()[..16])
+}
+
+/// Name and email of a commit signature. Invalid UTF-8 is replaced rather than
+/// dropped, so authors with such names still get an id.
+pub fn signature_identity(signature: &git2::Signature) -> (String, String) {
+    (
+        String::from_utf8_lossy(signature.name_bytes()).to_string(),
+        String::from_utf8_lossy(signature.email_bytes()).to_string(),
+    )
+}
+
+/// Author id of protocol versions before `AUTHOR_ID_VERSION` existed. Derived from
+/// `DefaultHasher`, so only reproducible with the toolchain that built the old ids.
+pub fn get_legacy_author_id(author_name: &str, author_email: &str) -> String {
     let mut s = DefaultHasher::new();
     author_name.hash(&mut s);
     author_email.hash(&mut s);
@@ -27,3 +56,30 @@ pub fn get_author_id(author_name: &String, author_email: &String) -> String {
     let author_id = format!("{:x}", s.finish());
     author_id
 }
+
+#[cfg(test)]
+mod tests {
+    use super::get_author_id;
+
+    /// Ids are persisted by clients, so they must never change within a version.
+    #[test]
+    fn author_ids_are_stable() {
+        assert_eq!(
+            get_author_id("Alice Liddell", "alice@x.org"),
+            "v1-581bde9c4a1b5229"
+        );
+        assert_eq!(get_author_id("Bob", "bob@x.org"), "v1-b100fad6669e4c4d");
+    }
+
+    #[test]
+    fn author_ids_normalize_whitespace_and_email_case() {
+        assert_eq!(
+            get_author_id("  Alice \t Liddell\n", " Alice@X.org "),
+            "v1-581bde9c4a1b5229"
+        );
+        assert_ne!(
+            get_author_id("alice liddell", "alice@x.org"),
+            "v1-581bde9c4a1b5229"
+        );
+    }
+}
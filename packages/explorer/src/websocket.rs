@@ -0,0 +1,165 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::Mutex;
+
+use base64::prelude::*;
+
+use crate::http::HttpRequest;
+use crate::transport::Transport;
+
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Upper bound for a single (reassembled) client message.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Completes the opening handshake of RFC 6455 for an upgrade request.
+pub fn accept(stream: &mut TcpStream, request: &HttpRequest) -> io::Result<()> {
+    let key = request
+        .header("sec-websocket-key")
+        .ok_or_else(|| invalid("missing Sec-WebSocket-Key"))?;
+
+    let mut sha = sha1_smol::Sha1::new();
+    sha.update(key.as_bytes());
+    sha.update(HANDSHAKE_GUID.as_bytes());
+    let accept = BASE64_STANDARD.encode(sha.digest().bytes());
+
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    )?;
+    stream.flush()
+}
+
+fn write_frame(stream: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut header = vec![0x80 | opcode];
+
+    match payload.len() {
+        len if len < 126 => header.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            header.push(126);
+            header.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            header.push(127);
+            header.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+
+    stream.write_all(&header)?;
+    stream.write_all(payload)?;
+    stream.flush()
+}
+
+/// Text messages over an accepted WebSocket connection.
+pub struct WebSocketTransport {
+    reader: Mutex<TcpStream>,
+    writer: Mutex<TcpStream>,
+}
+
+impl WebSocketTransport {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        Ok(WebSocketTransport {
+            reader: Mutex::new(stream.try_clone()?),
+            writer: Mutex::new(stream),
+        })
+    }
+
+    fn read_frame(reader: &mut TcpStream) -> io::Result<(bool, u8, Vec<u8>)> {
+        let mut head = [0u8; 2];
+        reader.read_exact(&mut head)?;
+
+        let fin = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0F;
+        let masked = head[1] & 0x80 != 0;
+
+        let len = match head[1] & 0x7F {
+            126 => {
+                let mut buf = [0u8; 2];
+                reader.read_exact(&mut buf)?;
+                u16::from_be_bytes(buf) as usize
+            }
+            127 => {
+                let mut buf = [0u8; 8];
+                reader.read_exact(&mut buf)?;
+                usize::try_from(u64::from_be_bytes(buf)).map_err(|_| invalid("frame too large"))?
+            }
+            len => len as usize,
+        };
+
+        if len > MAX_MESSAGE_SIZE {
+            return Err(invalid("frame too large"));
+        }
+
+        let mut mask = [0u8; 4];
+        if masked {
+            reader.read_exact(&mut mask)?;
+        }
+
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload)?;
+
+        if masked {
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        }
+
+        Ok((fin, opcode, payload))
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn read_message(&self) -> io::Result<Option<String>> {
+        let mut reader = self.reader.lock().unwrap();
+        let mut message = Vec::new();
+
+        loop {
+            let (fin, opcode, payload) = match Self::read_frame(&mut reader) {
+                Ok(frame) => frame,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            };
+
+            match opcode {
+                OP_TEXT | OP_BINARY | OP_CONTINUATION => {
+                    if message.len() + payload.len() > MAX_MESSAGE_SIZE {
+                        return Err(invalid("message too large"));
+                    }
+                    message.extend_from_slice(&payload);
+                    if fin {
+                        break;
+                    }
+                }
+                OP_PING => {
+                    write_frame(&mut *self.writer.lock().unwrap(), OP_PONG, &payload)?;
+                }
+                OP_PONG => {}
+                OP_CLOSE => {
+                    let _ = write_frame(&mut *self.writer.lock().unwrap(), OP_CLOSE, &[]);
+                    return Ok(None);
+                }
+                _ => return Err(invalid("unknown opcode")),
+            }
+        }
+
+        String::from_utf8(message)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn write_message(&self, message: &str) -> io::Result<()> {
+        write_frame(&mut *self.writer.lock().unwrap(), OP_TEXT, message.as_bytes())
+    }
+}
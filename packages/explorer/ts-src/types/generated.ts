@@ -1,64 +1,145 @@
 // This file has been generated by Specta. DO NOT EDIT.
 
-export type Author = { id: string; name: string; email: string; gravatarHash: string; numCommits: number }
+export type Author = { id: string; name: string; email: string; gravatarHash: string; isBot: boolean; numCommits: number; numCoAuthored: number; aliases: Identity[]; firstCommitTimestamp: number | null; lastCommitTimestamp: number | null; activeDays: number; linesAdded: number | null; linesRemoved: number | null; filesTouched: number | null }
 
 export type AuthorInfo = { id: string; name: string; email: string }
 
-export type Blame = { fileName: string; commits: { [key in string]: CommitInfo }; lines: BlameLine[] }
+export type AuthorOrder = "name" | "num_commits"
+
+export type AuthorPage = { authors: Author[]; nextCursor: string | null }
+
+export type Blame = { fileName: string; commits: { [key in string]: CommitInfo }; lines: BlameLine[]; ownership: { [key in string]: Ownership } }
 
 export type BlameLine = { lineNo: number; commitId: string; content: string }
 
-export type BlameParams = { rev: string; path: string; preview?: boolean | null; sinceRev?: string | null }
+export type BlameParams = { rev: string; path: string; preview?: boolean | null; sinceRev?: string | null; bots?: BotFilter | null }
+
+export type BotFilter = "include" | "exclude" | "separate"
 
 export type BranchInfo = { id: string; name: string; last_commit_id: string }
 
-export type Commit = { oid: string; aid: string; message: string; files: CommitFiles; timestamp: string }
+export type CancelParams = { id: number }
+
+export type Capabilities = { protocolVersion: number; crateVersion: string; authorIdVersion: number; methods: string[]; features: string[]; limits: Limits }
+
+export type CloseRepositoryParams = { handle: number }
 
-export type CommitFiles = { deleted: string[]; modified: string[]; added: string[]; renamed: ([string, string])[] }
+export type Command = { method: "get_commits_for_time_range"; params: GetCommitsForTimeRangeParams } | { method: "open_repository"; params: OpenRepositoryParams } | { method: "stream_authors"; params: StreamAuthorsParams } | { method: "get_authors"; params: GetAuthorsParams } | { method: "get_file_tree"; params: GetFileTreeParams } | { method: "stream_file_tree"; params: GetFileTreeParams } | { method: "get_branches"; params: NoParams } | { method: "get_git_graph"; params: NoParams } | { method: "get_blame"; params: BlameParams } | { method: "get_file_content"; params: GetFileContentParams } | { method: "get_commits_for_branch"; params: GetCommitsForBranchParams } | { method: "stream_commits"; params: StreamCommitsParams } | { method: "shutdown"; params: NoParams } | { method: "get_initial_data"; params: NoParams } | { method: "is_valid_rev"; params: IsValidRevParams } | { method: "get_commit"; params: GetCommitParams } | { method: "cancel"; params: CancelParams } | { method: "get_capabilities"; params: NoParams } | { method: "close_repository"; params: CloseRepositoryParams } | { method: "list_repositories"; params: NoParams } | { method: "get_diff"; params: GetDiffParams } | { method: "map_legacy_author_ids"; params: MapLegacyAuthorIdsParams }
 
-export type CommitInfo = { commitId: string; authorId: string; timestamp: string }
+export type Commit = { oid: string; aid: string; message: string; files: CommitFiles; changes: FileChange[]; stats: CommitStats; timestamp: string; diffMode: DiffMode; parsedMessage?: ParsedMessage }
+
+export type CommitFiles = { deleted: string[]; modified: string[]; added: string[]; renamed: ([string, string])[]; copied: ([string, string])[] }
+
+export type CommitInfo = { commitId: string; authorId: string; coAuthorIds: string[]; timestamp: string }
 
 export type CommitMeta = { oid: string; aid: string; message: string; timestamp: string }
 
+export type CommitOrder = "topo" | "time"
+
 export type CommitRange = { sinceCommit: Commit | null; untilCommit: Commit | null }
 
+export type CommitStats = { insertions: number | null; deletions: number | null; filesChanged: number }
+
 export type CommitTree = { graph: HistoryGraph; dot: string }
 
 export type CommitsForBranch = { start_commit: string; end_commit: string }
 
+export type ConventionalCommit = { type: string; scope: string | null; breaking: boolean }
+
+export type DataResponse<T> = { id?: number | null; data: T; end?: boolean; cancelled?: boolean }
+
+export type DiffHunk = { header: string; oldStart: number; oldLines: number; newStart: number; newLines: number; lines: DiffLine[] }
+
+export type DiffLine = { origin: string; content: string; oldLineno: number | null; newLineno: number | null }
+
+export type DiffMode = "first_parent" | "combined" | "per_parent"
+
+export type DiffResult = { from: string | null; to: string; files: FileDiff[]; patch?: string }
+
+export type ErrorCode = "no_repository_open" | "unknown_repository" | "rev_not_found" | "path_not_found" | "invalid_params" | "git_error" | "internal" | "cancelled"
+
+export type ErrorResponse = { id?: number | null; error: string; code: ErrorCode; git?: GitErrorInfo | null }
+
+export type FileChange = { path: string; oldPath: string | null; status: FileStatus; insertions: number | null; deletions: number | null; binary: boolean; oldOid: string | null; newOid: string | null; parent: string | null }
+
+export type FileDiff = { path: string; oldPath: string | null; status: FileStatus; binary: boolean; hunks: DiffHunk[] }
+
+export type FileStatus = "added" | "deleted" | "modified" | "renamed" | "copied" | "type_changed"
+
 export type FileTreeNode = { path: string[]; kind?: any | null; loading?: boolean | null }
 
-export type GetCommitParams = { rev: string }
+export type GetAuthorsParams = { limit?: number | null; cursor?: string | null; order?: AuthorOrder | null; revs?: string[] | null; paths?: string[] | null; since?: number | null; until?: number | null; bots?: BotFilter | null; lineStats?: boolean | null }
+
+export type GetAuthorsResult = Author[] | AuthorPage
+
+export type GetCommitParams = { rev: string; renameThreshold?: number | null; copies?: boolean | null; copyThreshold?: number | null; diffMode?: DiffMode | null; parseMessage?: boolean | null; lineStats?: boolean | null }
 
 export type GetCommitsForBranchParams = { branch: string }
 
 export type GetCommitsForTimeRangeParams = { branch: string; startSeconds: number; endSeconds: number }
 
+export type GetDiffParams = { rev: string; from?: string | null; paths?: string[] | null; contextLines?: number | null; whitespace?: WhitespaceMode | null; raw?: boolean | null }
+
 export type GetFileContentParams = { path: string; rev: string }
 
 export type GetFileContentResult = { content: string; encoding: string; lfs?: boolean | null }
 
 export type GetFileTreeParams = { rev: string }
 
+export type GitErrorInfo = { class: string; code: string }
+
 export type GitGraphCommitInfo = { oid: string; aid: string; timestamp: string; message: string; is_merge: boolean; parents: (string | null)[]; children: string[] }
 
 export type HistoryGraph = { commit_indices: { [key in string]: number }; commits: GitGraphCommitInfo[]; branches: BranchInfo[] }
 
+export type Identity = { name: string; email: string }
+
 export type Infallible = never
 
 export type InitialDataResult = { currentBranch: string; lastCommit: Commit; firstCommit: Commit; remotes: Remote[]; branches: string[]; tags: string[] }
 
 export type IsValidRevParams = { rev: string }
 
+export type LegacyAuthorIds = { ids: { [key in string]: string | null } }
+
+export type Limits = { concurrentRequests: number; graphMessageLength: number }
+
+export type MapLegacyAuthorIdsParams = { ids: string[] }
+
 export type NoParams = Record<string, never>
 
-export type OpenRepositoryParams = { path: string }
+export type OpenRepositoryParams = { path: string; mailmapRev?: string | null; botPatterns?: string[] | null }
+
+export type OpenRepositoryResult = { success: boolean; handle: number }
+
+export type Ownership = { lines: number; coAuthoredLines: number }
 
-export type OpenRepositoryResult = { success: boolean }
+export type ParsedMessage = { subject: string; body: string; conventional: ConventionalCommit | null; trailers: Trailer[] }
 
 export type Remote = { name: string; url: string }
 
-export type StreamAuthorsParams = Record<string, never>
+export type RepositoryInfo = { handle: number; path: string; isDefault: boolean }
+
+export type Request = ({ id?: number | null; repo?: number | null }) & Command
+
+export type Response = ErrorResponse | DataResponse<any>
+
+export type StreamAuthorsParams = { revs?: string[] | null; paths?: string[] | null; since?: number | null; until?: number | null; bots?: BotFilter | null; lineStats?: boolean | null }
+
+export type StreamCommitsParams = { limit?: number | null; cursor?: string | null; order?: CommitOrder | null; revs?: string[] | null; authors?: string[] | null; paths?: string[] | null; since?: number | null; until?: number | null; message?: string | null; merges?: boolean | null; bots?: BotFilter | null; renameThreshold?: number | null; copies?: boolean | null; copyThreshold?: number | null; diffMode?: DiffMode | null; parseMessage?: boolean | null; lineStats?: boolean | null }
+
+export type StreamCommitsResult = { nextCursor: string | null }
+
+export type Trailer = { key: string; value: string }
+
+export type WhitespaceMode = "strict" | "ignore_all" | "ignore_change" | "ignore_eol"
+
+export type MethodParamsMap = { get_commits_for_time_range: GetCommitsForTimeRangeParams; open_repository: OpenRepositoryParams; stream_authors: StreamAuthorsParams; get_authors: GetAuthorsParams; get_file_tree: GetFileTreeParams; stream_file_tree: GetFileTreeParams; get_branches: NoParams; get_git_graph: NoParams; get_blame: BlameParams; get_file_content: GetFileContentParams; get_commits_for_branch: GetCommitsForBranchParams; stream_commits: StreamCommitsParams; shutdown: NoParams; get_initial_data: NoParams; is_valid_rev: IsValidRevParams; get_commit: GetCommitParams; cancel: CancelParams; get_capabilities: NoParams; close_repository: CloseRepositoryParams; list_repositories: NoParams; get_diff: GetDiffParams; map_legacy_author_ids: MapLegacyAuthorIdsParams; }
+
+export type MethodResultMap = { get_commits_for_time_range: CommitRange; open_repository: OpenRepositoryResult; stream_authors: null; get_authors: GetAuthorsResult; get_file_tree: FileTreeNode[]; stream_file_tree: string; get_branches: string[]; get_git_graph: CommitTree; get_blame: Blame; get_file_content: GetFileContentResult; get_commits_for_branch: CommitsForBranch; stream_commits: StreamCommitsResult; shutdown: void; get_initial_data: InitialDataResult; is_valid_rev: boolean; get_commit: Commit; cancel: boolean; get_capabilities: Capabilities; close_repository: boolean; list_repositories: RepositoryInfo[]; get_diff: DiffResult; map_legacy_author_ids: LegacyAuthorIds; }
+
+export type MethodStreamItemMap = { stream_authors: Author; stream_file_tree: FileTreeNode; stream_commits: Commit; }
 
-export type StreamCommitsParams = Record<string, never>
+export type Method = keyof MethodParamsMap
 
+export type StreamingMethod = keyof MethodStreamItemMap
@@ -1,70 +1,40 @@
 import type * as gen from "./generated";
 
-export type Methods = keyof ParameterPayloadMap;
+export type Methods = gen.Method;
 
-export type ParameterPayloadMap = {
-  open_repository: gen.OpenRepositoryParams;
-  stream_authors: gen.StreamAuthorsParams;
-  get_authors: gen.NoParams;
-  get_file_tree: gen.GetFileTreeParams;
-  get_branches: gen.NoParams;
-  get_git_graph: gen.NoParams;
-  get_blame: gen.BlameParams;
-  get_file_content: gen.GetFileContentParams;
-  get_commits_for_branch: gen.GetCommitsForBranchParams;
-  stream_commits: gen.StreamCommitsParams;
-  get_commits_for_time_range: gen.GetCommitsForTimeRangeParams;
-  shutdown: gen.NoParams;
-  get_initial_data: gen.NoParams;
-  is_valid_rev: gen.IsValidRevParams;
-  get_commit: gen.GetCommitParams;
-};
+export type ParameterPayloadMap = gen.MethodParamsMap;
 
 export type Params<M extends Methods> = ParameterPayloadMap[M];
 
-type IntermediateCallbackPayloadMap = {
-  stream_authors: gen.Author;
-  stream_file_tree: gen.FileTreeNode;
-  stream_commits: gen.CommitInfo;
-};
-
-export type IntermediatePayload<M extends Methods> = M extends keyof IntermediateCallbackPayloadMap
-  ? IntermediateCallbackPayloadMap[M]
+export type IntermediatePayload<M extends Methods> = M extends gen.StreamingMethod
+  ? gen.MethodStreamItemMap[M]
   : never;
 
-type FinalCallbackPayloadMap = {
-  get_authors: gen.Author[];
-  open_repository: gen.OpenRepositoryResult;
-  stream_authors: void;
-  stream_file_tree: void;
-  stream_commits: void;
-  get_file_tree: gen.FileTreeNode[];
-  get_branches: string[];
-  get_git_graph: gen.CommitTree;
-  get_blame: gen.Blame;
-  get_file_content: gen.GetFileContentResult;
-  get_commits_for_branch: gen.Commit[];
-  get_initial_data: gen.InitialDataResult;
-  is_valid_rev: boolean;
-  get_commit: gen.Commit;
-  shutdown: void;
-  get_commits_for_time_range: gen.CommitRange;
-};
+export type FinalPayload<M extends Methods> = gen.MethodResultMap[M];
 
-export type FinalPayload<M extends Methods> = M extends keyof FinalCallbackPayloadMap
-  ? FinalCallbackPayloadMap[M]
-  : never;
+export type ErrorPayload = gen.ErrorResponse;
 
 export type Response<M extends Methods> =
   | {
+      id?: number;
       error: Error;
     }
   | {
+      id?: number;
       error?: undefined;
       data: FinalPayload<M>;
       end: true;
+      cancelled?: false;
     }
   | {
+      id?: number;
+      error?: undefined;
+      data: null;
+      end: true;
+      cancelled: true;
+    }
+  | {
+      id?: number;
       error?: undefined;
       data: IntermediatePayload<M>;
       end?: false;
@@ -72,8 +42,11 @@ export type Response<M extends Methods> =
 
 export type Callback<M extends Methods> = (resp: Response<M>) => void;
 
+/** Handle returned by `open_repository`; requests without one use the default repository. */
+export type RepoHandle = number;
+
 export interface ExplorerI {
-  send<M extends Methods>(method: M, params: Params<M>, cb: Callback<M>): void;
+  send<M extends Methods>(method: M, params: Params<M>, cb: Callback<M>, repo?: RepoHandle): void;
 }
 
 export interface ExplorerPoolMetrics {
@@ -904,6 +904,7 @@ export class MaestroWorker extends EventEmitter<MaestroWorkerEvents, MaestroWork
           ...commit.files.modified,
           ...commit.files.deleted,
           ...commit.files.renamed.map((file) => file[1]),
+          ...commit.files.copied.map((file) => file[1]),
         ]);
 
         return this.availableFiles!.filter((node) => {
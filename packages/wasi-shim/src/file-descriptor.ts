@@ -20,6 +20,10 @@ export class Fd {
     return { ret: -1, fdstat: null };
   }
 
+  fd_fdstat_set_flags(flags: number): number {
+    return wasi.ERRNO_NOTSUP;
+  }
+
   fd_prestat_get(): RetVal_prestat {
     return { ret: -1, prestat: null };
   }
@@ -1,7 +1,7 @@
 import { createLogger, Logger } from "@giz/logging";
 
 import { Fd, RetVal_nread, RetVal_nwritten } from "./file-descriptor";
-import { Iovec } from "./wasi-defs";
+import { ERRNO_AGAIN, FDFLAGS_NONBLOCK, Iovec } from "./wasi-defs";
 
 export class ConsolePipe extends Fd {
   logger: Logger;
@@ -36,6 +36,7 @@ export class StdIoPipe extends Fd {
   private allData = "";
   private data = "";
   private listener: ((data: string) => void) | undefined = undefined;
+  private flags = 0;
 
   constructor(public identifier: string = "") {
     super();
@@ -92,7 +93,20 @@ export class StdIoPipe extends Fd {
     }
   }
 
+  fd_fdstat_set_flags(flags: number): number {
+    this.flags = flags;
+    return 0;
+  }
+
   async fd_read(view8: Uint8Array, iovs: Iovec[]): Promise<RetVal_nread> {
+    if ((this.flags & FDFLAGS_NONBLOCK) !== 0) {
+      // let writes that were posted in the meantime arrive before reporting none
+      await new Promise((resolve) => setTimeout(resolve, 0));
+      if (this.data.length === 0) {
+        return { ret: ERRNO_AGAIN, nread: 0 };
+      }
+    }
+
     let nread = 0;
     for (const iovec of iovs) {
       const data = await this.readBytes(iovec.buf_len);
@@ -348,8 +348,10 @@ export class WASI {
         }
         return wasi.ERRNO_SUCCESS;
       },
-      fd_fdstat_set_flags(fd: number, fdstat_ptr: number): number {
-        self.logger.error("fd_fdstat_set not implemented");
+      fd_fdstat_set_flags(fd: number, flags: number): number {
+        if (self.hasFd(fd)) {
+          return self.getFd(fd).fd_fdstat_set_flags(flags);
+        }
         return wasi.ERRNO_BADF;
       },
       fd_prestat_get(fd: number, prestat_ptr: number): number {